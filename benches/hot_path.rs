@@ -0,0 +1,99 @@
+//! Benchmarks of publishing and switching to the latest block, the paths every update goes through.
+//! Run with `cargo bench --bench hot_path`.
+//! It only compares backends built from the same tree, to measure a change save a baseline before it with `cargo bench --bench hot_path -- --save-baseline before` and compare after it with `-- --baseline before`.
+//! `atomic_arc` is the generic cycler with an `Arc` per `AtomicRwLock` slot, what `AtomicCycler` was before its slots moved into one allocation.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use cycler::atomic_rw_lock::AtomicRwLock;
+use cycler::generic::{self, GenericCyclerReader, GenericCyclerWriter};
+use cycler::{atomic_cycler, rw_lock_cycler, CyclerReader, CyclerWriterDefault, ReadAccess, Slot, WriteAccess};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// A small block so the benchmarks measure the cycler rather than cloning.
+#[derive(Clone, Default, Debug)]
+struct Data([u64; 4]);
+impl ReadAccess for Data {
+    type Read = Self;
+
+    fn read_data(&self) -> &Self::Read {
+        self
+    }
+}
+impl WriteAccess for Data {
+    type Write = Self;
+
+    fn write_data(&self) -> &Self::Write {
+        self
+    }
+
+    fn write_data_mut(&mut self) -> &mut Self::Write {
+        self
+    }
+}
+
+/// The lock of every slot of `atomic_arc`.
+type ArcSlot = AtomicRwLock<Slot<Data>, AtomicU32>;
+
+/// Builds the `atomic_arc` cycler.
+fn build_atomic_arc(values: [Data; 3]) -> (GenericCyclerWriter<Data, ArcSlot>, GenericCyclerReader<Data, ArcSlot>) {
+    generic::build_single_reader(values)
+}
+
+/// Benchmarks `write_next` then `read_latest` on one thread, the cost of a publish and switch without contention.
+fn bench_uncontended<W, R>(c: &mut Criterion, name: &str, (mut writer, mut reader): (W, R))
+where
+    W: CyclerWriterDefault<Data>,
+    R: CyclerReader<Data>,
+{
+    let mut group = c.benchmark_group("uncontended");
+    group.bench_function(BenchmarkId::new("write_next", name), |b| {
+        b.iter(|| {
+            writer.write_data_mut().0[0] += 1;
+            black_box(writer.write_next())
+        })
+    });
+    group.bench_function(BenchmarkId::new("read_latest", name), |b| {
+        b.iter(|| {
+            writer.write_next();
+            black_box(reader.read_latest())
+        })
+    });
+    group.finish();
+}
+
+/// Benchmarks `read_latest` while another thread publishes as fast as it can.
+fn bench_contended<W, R>(c: &mut Criterion, name: &str, (mut writer, mut reader): (W, R))
+where
+    W: CyclerWriterDefault<Data> + Send + 'static,
+    R: CyclerReader<Data>,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let handle = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                writer.write_data_mut().0[0] += 1;
+                writer.write_next();
+            }
+        })
+    };
+    c.bench_function(&format!("contended/read_latest/{}", name), |b| b.iter(|| black_box(reader.read_latest())));
+    stop.store(true, Ordering::Relaxed);
+    handle.join().unwrap();
+}
+
+fn hot_path(c: &mut Criterion) {
+    let values = || [Data::default(), Data::default(), Data::default()];
+    bench_uncontended(c, "rw_lock", rw_lock_cycler::build_single_reader(values()));
+    bench_uncontended(c, "atomic", atomic_cycler::build_single_reader(values()));
+    bench_uncontended(c, "atomic_arc", build_atomic_arc(values()));
+    bench_contended(c, "rw_lock", rw_lock_cycler::build_single_reader(values()));
+    bench_contended(c, "atomic", atomic_cycler::build_single_reader(values()));
+    bench_contended(c, "atomic_arc", build_atomic_arc(values()));
+}
+
+criterion_group!(benches, hot_path);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Parsing of the `#[cycler(...)]` attributes.
+
+use syn::{Attribute, Error, Field, Fields, Result};
+
+/// Which readers can see a field.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum FieldAccess {
+    /// No attribute was given.
+    Unmarked,
+    /// `#[cycler(read)]`
+    Read,
+    /// `#[cycler(write_only)]`
+    WriteOnly,
+}
+
+/// The parsed `#[cycler(...)]` attributes of a field.
+#[derive(Debug)]
+pub(crate) struct FieldAttributes {
+    pub(crate) access: FieldAccess,
+    /// `#[cycler(dirty)]`, the field is the `DirtyFields` tracker.
+    pub(crate) dirty: bool,
+}
+impl FieldAttributes {
+    pub(crate) fn parse(field: &Field) -> Result<Self> {
+        let mut out = Self { access: FieldAccess::Unmarked, dirty: false };
+        for attr in cycler_attributes(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("dirty") {
+                    out.dirty = true;
+                    return Ok(());
+                }
+                let access = if meta.path.is_ident("read") {
+                    FieldAccess::Read
+                } else if meta.path.is_ident("write_only") {
+                    FieldAccess::WriteOnly
+                } else {
+                    return Err(meta.error("unknown cycler field attribute, expected `read`, `write_only` or `dirty`"));
+                };
+                if out.access != FieldAccess::Unmarked && out.access != access {
+                    return Err(meta.error("a field can't be both `read` and `write_only`"));
+                }
+                out.access = access;
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+/// Parses the attributes of every field, erroring if more than one field is marked `#[cycler(dirty)]`.
+pub(crate) fn parse_fields(fields: &Fields) -> Result<Vec<FieldAttributes>> {
+    let attributes = fields.iter().map(FieldAttributes::parse).collect::<Result<Vec<_>>>()?;
+    if let Some((field, _)) = fields.iter().zip(&attributes).filter(|(_, attributes)| attributes.dirty).nth(1) {
+        return Err(Error::new_spanned(field, "only one field can be marked `#[cycler(dirty)]`"));
+    }
+    Ok(attributes)
+}
+
+fn cycler_attributes(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("cycler"))
+}
@@ -0,0 +1,74 @@
+//! `#[derive(CloneFrom)]`
+
+use crate::attributes::parse_fields;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Error, Fields, Index, Member, Result};
+
+pub(crate) fn derive(mut input: DeriveInput) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new_spanned(&input.ident, "`CloneFrom` can only be derived for structs")),
+    };
+    let attributes = parse_fields(fields)?;
+    let members: Vec<Member> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        })
+        .collect();
+    let dirty = members.iter().zip(&attributes).find(|(_, attributes)| attributes.dirty).map(|(member, _)| member);
+
+    let clone = match fields {
+        Fields::Named(_) => quote! { Self { #(#members: ::core::clone::Clone::clone(&self.#members),)* } },
+        Fields::Unnamed(_) => quote! { Self(#(::core::clone::Clone::clone(&self.#members),)*) },
+        Fields::Unit => quote! { Self },
+    };
+    let clone_dirty = dirty.map(|dirty| {
+        let clone_fields = members.iter().zip(&attributes).enumerate().filter(|(_, (_, attributes))| !attributes.dirty).map(|(index, (member, _))| {
+            quote! {
+                if self.#dirty.differs(&source.#dirty, #index) {
+                    ::core::clone::Clone::clone_from(&mut self.#member, &source.#member);
+                }
+            }
+        });
+        quote! {
+            #[inline]
+            fn clone_dirty(&mut self, source: &Self) {
+                #(#clone_fields)*
+                ::core::clone::Clone::clone_from(&mut self.#dirty, &source.#dirty);
+            }
+        }
+    });
+
+    let type_params: Vec<_> = input.generics.type_params().map(|param| param.ident.clone()).collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        where_clause.predicates.push(parse_quote!(#param: ::core::clone::Clone));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let clone_dirty = clone_dirty.map(|clone_dirty| {
+        quote! {
+            impl #impl_generics ::cycler::CloneDirty for #name #ty_generics #where_clause {
+                #clone_dirty
+            }
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::core::clone::Clone for #name #ty_generics #where_clause {
+            #[inline]
+            fn clone(&self) -> Self {
+                #clone
+            }
+
+            #[inline]
+            fn clone_from(&mut self, source: &Self) {
+                #(::core::clone::Clone::clone_from(&mut self.#members, &source.#members);)*
+            }
+        }
+        #clone_dirty
+    })
+}
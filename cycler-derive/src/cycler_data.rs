@@ -0,0 +1,167 @@
+//! `#[derive(CyclerData)]`
+
+use crate::attributes::{parse_fields, FieldAccess};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Result};
+
+pub(crate) fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => Some(&data.fields),
+        _ => None,
+    };
+    let attributes = match fields {
+        Some(fields) => parse_fields(fields)?,
+        None => Vec::new(),
+    };
+    let needs_read_view = attributes.iter().any(|attributes| attributes.access != FieldAccess::Unmarked);
+    let needs_write_view = attributes.iter().any(|attributes| attributes.dirty);
+    let view_fields = match fields {
+        Some(Fields::Named(fields)) => Some(fields),
+        _ => None,
+    };
+
+    let vis = &input.vis;
+    let generics = &input.generics;
+
+    let read_access = if needs_read_view {
+        let fields = view_fields.ok_or_else(|| {
+            Error::new_spanned(&input.ident, "read views can only be generated for structs with named fields")
+        })?;
+        let explicit_read = attributes.iter().any(|attributes| attributes.access == FieldAccess::Read);
+        let accessors = fields.named.iter().zip(&attributes).filter_map(|(field, attributes)| {
+            let visible = match attributes.access {
+                FieldAccess::Read => true,
+                FieldAccess::WriteOnly => false,
+                FieldAccess::Unmarked => !explicit_read && !attributes.dirty,
+            };
+            if !visible {
+                return None;
+            }
+            let vis = &field.vis;
+            let ident = field.ident.as_ref().unwrap();
+            let ty = &field.ty;
+            let doc = format!("Gets the `{}` field.", ident);
+            Some(quote! {
+                #[doc = #doc]
+                #[inline]
+                #vis fn #ident(&self) -> &#ty {
+                    &self.0.#ident
+                }
+            })
+        });
+
+        let read_name = format_ident!("{}Read", name);
+        let doc = format!("Read view of `{}` only exposing the fields readers can see.", name);
+        quote! {
+            #[doc = #doc]
+            #[repr(transparent)]
+            #vis struct #read_name #generics (#name #ty_generics) #where_clause;
+            impl #impl_generics #read_name #ty_generics #where_clause {
+                #(#accessors)*
+            }
+            impl #impl_generics ::cycler::ReadAccess for #name #ty_generics #where_clause {
+                type Read = #read_name #ty_generics;
+
+                #[inline]
+                #[allow(unsafe_code)]
+                fn read_data(&self) -> &Self::Read {
+                    // Safety: the read view is a `#[repr(transparent)]` wrapper so has the same layout.
+                    unsafe { &*(self as *const Self as *const #read_name #ty_generics) }
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics ::cycler::ReadAccess for #name #ty_generics #where_clause {
+                type Read = Self;
+
+                #[inline]
+                fn read_data(&self) -> &Self::Read {
+                    self
+                }
+            }
+        }
+    };
+
+    let write_access = if needs_write_view {
+        let fields = view_fields.ok_or_else(|| {
+            Error::new_spanned(&input.ident, "dirty tracking is only supported for structs with named fields")
+        })?;
+        let dirty = fields.named.iter().zip(&attributes).find(|(_, attributes)| attributes.dirty).unwrap().0.ident.as_ref().unwrap();
+        let accessors = fields.named.iter().zip(&attributes).enumerate().filter(|(_, (_, attributes))| !attributes.dirty).map(|(index, (field, _))| {
+            let vis = &field.vis;
+            let ident = field.ident.as_ref().unwrap();
+            let ident_mut = format_ident!("{}_mut", ident);
+            let ty = &field.ty;
+            let doc = format!("Gets the `{}` field.", ident);
+            let doc_mut = format!("Gets the `{}` field mutably, marking it as changed.", ident);
+            quote! {
+                #[doc = #doc]
+                #[inline]
+                #vis fn #ident(&self) -> &#ty {
+                    &self.0.#ident
+                }
+
+                #[doc = #doc_mut]
+                #[inline]
+                #vis fn #ident_mut(&mut self) -> &mut #ty {
+                    self.0.#dirty.mark(#index);
+                    &mut self.0.#ident
+                }
+            }
+        });
+
+        let write_name = format_ident!("{}Write", name);
+        let doc = format!("Write view of `{}` marking fields as changed when they are accessed mutably.", name);
+        quote! {
+            #[doc = #doc]
+            #[repr(transparent)]
+            #vis struct #write_name #generics (#name #ty_generics) #where_clause;
+            impl #impl_generics #write_name #ty_generics #where_clause {
+                #(#accessors)*
+            }
+            impl #impl_generics ::cycler::WriteAccess for #name #ty_generics #where_clause {
+                type Write = #write_name #ty_generics;
+
+                #[inline]
+                #[allow(unsafe_code)]
+                fn write_data(&self) -> &Self::Write {
+                    // Safety: the write view is a `#[repr(transparent)]` wrapper so has the same layout.
+                    unsafe { &*(self as *const Self as *const #write_name #ty_generics) }
+                }
+
+                #[inline]
+                #[allow(unsafe_code)]
+                fn write_data_mut(&mut self) -> &mut Self::Write {
+                    // Safety: the write view is a `#[repr(transparent)]` wrapper so has the same layout.
+                    unsafe { &mut *(self as *mut Self as *mut #write_name #ty_generics) }
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics ::cycler::WriteAccess for #name #ty_generics #where_clause {
+                type Write = Self;
+
+                #[inline]
+                fn write_data(&self) -> &Self::Write {
+                    self
+                }
+
+                #[inline]
+                fn write_data_mut(&mut self) -> &mut Self::Write {
+                    self
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #read_access
+        #write_access
+    })
+}
@@ -0,0 +1,41 @@
+//! Derive macros for the `cycler` crate.
+//! These are re-exported by `cycler` when its `derive` feature is enabled.
+#![warn(missing_debug_implementations, rust_2018_idioms, missing_docs, unused_import_braces)]
+
+mod attributes;
+mod clone_from;
+mod cycler_data;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derives `ReadAccess` and `WriteAccess`.
+///
+/// By default both `ReadAccess::Read` and `WriteAccess::Write` are `Self`.
+///
+/// If any field of a struct is marked with `#[cycler(read)]` or `#[cycler(write_only)]` a read view named `<Name>Read` is generated and used as `ReadAccess::Read`.
+/// The read view only has accessor functions for the fields readers can see, these are named after the fields and have the same visibility.
+/// If any field is marked `#[cycler(read)]` only the marked fields are visible to readers, otherwise every field not marked `#[cycler(write_only)]` is visible.
+///
+/// If a field is marked `#[cycler(dirty)]` it must be a `cycler::DirtyFields` and a write view named `<Name>Write` is generated and used as `WriteAccess::Write`.
+/// The write view has a `<field>` and `<field>_mut` accessor for every other field, the `_mut` accessor marks the field as changed.
+/// Together with `#[derive(CloneFrom)]` this implements `cycler::CloneDirty`, which only copies the fields that changed.
+///
+/// The views are `#[repr(transparent)]` wrappers around the data so the generated code contains `unsafe` and can't be used in crates that forbid it.
+#[proc_macro_derive(CyclerData, attributes(cycler))]
+pub fn derive_cycler_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    cycler_data::derive(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives `Clone` with a `clone_from` that calls `clone_from` on every field, reusing their allocations.
+///
+/// If a field is marked `#[cycler(dirty)]` it must be a `cycler::DirtyFields` and `cycler::CloneDirty` is also implemented, only cloning the fields that have changed since `self` was a copy of the source.
+/// Fields are marked as changed by the write view `#[derive(CyclerData)]` generates.
+/// `clone_from` still clones every field so it behaves like `clone` for unrelated values.
+/// Like the derived `Clone`, every type parameter is required to implement `Clone`.
+#[proc_macro_derive(CloneFrom, attributes(cycler))]
+pub fn derive_clone_from(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    clone_from::derive(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
@@ -0,0 +1,86 @@
+use cycler::{build_single_reader_default, clone_dirty, CloneDirty, CyclerReader, CyclerWriterFn, DirtyFields, ReadAccess, WriteAccess};
+use cycler_derive::{CloneFrom, CyclerData};
+
+/// Counts how many times it has been cloned into.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Counted(usize);
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.0 = source.0 + 1;
+    }
+}
+
+#[derive(CloneFrom, Debug, Default, Eq, PartialEq)]
+struct Plain<T> {
+    value: T,
+    name: String,
+}
+
+#[derive(CloneFrom, Debug, Default, Eq, PartialEq)]
+struct Tuple(usize, Vec<usize>);
+
+#[derive(CyclerData, CloneFrom, Debug, Default, Eq, PartialEq)]
+struct Tracked {
+    a: Counted,
+    b: Counted,
+    #[cycler(dirty)]
+    dirty: DirtyFields,
+}
+
+#[test]
+fn plain_test() {
+    let source = Plain { value: 10, name: "Test".to_string() };
+    let mut target = Plain { value: 0, name: String::with_capacity(100) };
+    target.clone_from(&source);
+    assert_eq!(target, source);
+    assert!(target.name.capacity() >= 100);
+    assert_eq!(source.clone(), source);
+
+    let source = Tuple(10, vec![1, 2, 3]);
+    let mut target = Tuple::default();
+    target.clone_from(&source);
+    assert_eq!(target, source);
+}
+
+#[test]
+fn dirty_test() {
+    let (mut writer, mut reader) = build_single_reader_default::<Tracked>();
+    writer.write_data_mut().a_mut().0 = 10;
+    writer.write_next_fn(clone_dirty);
+    // Only `a` changed so only `a` is cloned into the recycled block.
+    assert_eq!(*writer.write_data().a(), Counted(11));
+    assert_eq!(*writer.write_data().b(), Counted(0));
+    reader.read_latest();
+    assert_eq!(reader.read_data().a, Counted(10));
+
+    writer.write_data_mut().b_mut().0 = 20;
+    writer.write_next_fn(clone_dirty);
+    // The recycled block missed the change to `a` and `b`.
+    assert_eq!(*writer.write_data().a(), Counted(12));
+    assert_eq!(*writer.write_data().b(), Counted(21));
+    writer.write_next_fn(clone_dirty);
+    // The recycled block was written last and nothing changed since so nothing is cloned.
+    assert_eq!(*writer.write_data().a(), Counted(11));
+    assert_eq!(*writer.write_data().b(), Counted(20));
+}
+
+#[test]
+fn unrelated_test() {
+    let mut source = Tracked::default();
+    source.write_data_mut().a_mut().0 = 10;
+    let mut target = Tracked::default();
+    target.write_data_mut().a_mut().0 = 20;
+    target.b.0 = 30;
+    // The stamps match but the values were never copies of each other, `clone_from` still clones every field.
+    target.clone_from(&source);
+    assert_eq!(target, Tracked { a: Counted(11), b: Counted(1), dirty: source.dirty.clone() });
+
+    let mut target = Tracked::default();
+    target.write_data_mut().a_mut().0 = 20;
+    target.clone_dirty(&source);
+    assert_eq!(target.a, Counted(20));
+}
@@ -0,0 +1,60 @@
+use cycler::{build_single_reader_default, CyclerReader, CyclerWriterDefault, ReadAccess, WriteAccess};
+use cycler_derive::CyclerData;
+
+#[derive(CyclerData, Clone, Default, Debug, Eq, PartialEq)]
+struct Plain {
+    value: usize,
+}
+
+#[derive(CyclerData, Clone, Default)]
+struct WriteOnly {
+    value: usize,
+    name: String,
+    #[cycler(write_only)]
+    log: Vec<usize>,
+}
+
+#[derive(CyclerData, Clone, Default)]
+struct Explicit<T>
+where
+    T: Clone,
+{
+    #[cycler(read)]
+    value: T,
+    hidden: usize,
+}
+
+#[test]
+fn plain_test() {
+    let (mut writer, mut reader) = build_single_reader_default::<Plain>();
+    writer.write_data_mut().value = 10;
+    writer.write_next();
+    reader.read_latest();
+    assert_eq!(*reader.read_data(), Plain { value: 10 });
+}
+
+#[test]
+fn write_only_test() {
+    let (mut writer, mut reader) = build_single_reader_default::<WriteOnly>();
+    let data = writer.write_data_mut();
+    data.value = 10;
+    data.name.push_str("Test");
+    data.log.push(10);
+    writer.write_next();
+    assert_eq!(writer.write_data().log, [10]);
+    reader.read_latest();
+    assert_eq!(*reader.read_data().value(), 10);
+    assert_eq!(reader.read_data().name(), "Test");
+}
+
+#[test]
+fn explicit_read_test() {
+    let (mut writer, mut reader) = build_single_reader_default::<Explicit<String>>();
+    let data = writer.write_data_mut();
+    data.value.push_str("Test");
+    data.hidden = 10;
+    writer.write_next();
+    assert_eq!(writer.write_data().hidden, 10);
+    reader.read_latest();
+    assert_eq!(reader.read_data().value(), "Test");
+}
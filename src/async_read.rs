@@ -0,0 +1,104 @@
+//! Futures and streams built on `CyclerReaderAsync`.
+//! These don't depend on any runtime, they are woken directly by the writer when it publishes.
+
+use crate::traits::{CyclerReaderAsync, ReadAccess};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future returned by `CyclerReaderAsync::read_latest_async`.
+/// Resolves once the reader has moved to a block newer than the one it held.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct ReadLatestFuture<'a, T, R>
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T> + ?Sized,
+{
+    reader: &'a mut R,
+    phantom: PhantomData<fn() -> T>,
+}
+impl<'a, T, R> ReadLatestFuture<'a, T, R>
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T> + ?Sized,
+{
+    pub(crate) fn new(reader: &'a mut R) -> Self {
+        Self { reader, phantom: PhantomData }
+    }
+}
+impl<'a, T, R> Future for ReadLatestFuture<'a, T, R>
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T> + ?Sized,
+{
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.reader.poll_read_latest(cx)
+    }
+}
+impl<'a, T, R> Drop for ReadLatestFuture<'a, T, R>
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T> + ?Sized,
+{
+    fn drop(&mut self) {
+        self.reader.cancel_read_latest();
+    }
+}
+
+/// `futures_core::Stream` adapter over a cycler reader, requires the `stream` feature.
+/// Each item is yielded after the reader has moved to a newly published block, the item being the version of that block.
+/// The data of the block can be accessed through `ReaderStream::reader`.
+#[cfg(feature = "stream")]
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct ReaderStream<T, R> {
+    reader: R,
+    phantom: PhantomData<fn() -> T>,
+}
+#[cfg(feature = "stream")]
+impl<T, R> ReaderStream<T, R>
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T>,
+{
+    /// Creates a stream from a reader.
+    pub fn new(reader: R) -> Self {
+        Self { reader, phantom: PhantomData }
+    }
+
+    /// Gets the wrapped reader.
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets the wrapped reader mutably.
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Gets the wrapped reader back.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+#[cfg(feature = "stream")]
+impl<T, R> Unpin for ReaderStream<T, R> {}
+#[cfg(feature = "stream")]
+impl<T, R> futures_core::Stream for ReaderStream<T, R>
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T>,
+{
+    type Item = u64;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.reader.poll_read_latest(cx) {
+            Poll::Ready(()) => Poll::Ready(Some(self.reader.version())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -1,58 +1,186 @@
 //! The `AtomicCycler` uses a custom atomic read/write lockless lock to keep track of reading and writing to each block without using unsafe code.
 //! This implementation is faster than `RwLockCycler` but it relies on a custom lock that is filled with unsafe code.
+//! The slots are stored in the slot table of the cycler, those it is built with in a single allocation, and guards borrow them through the `Arc` of the cycler the writer or reader holds.
+//! Switching slots is then only the compare exchange and decrement of the two locks, without an `Arc` reference count per slot.
 
-mod builder;
-mod reader;
-mod writer;
+use crate::atomic_rw_lock::{AtomicRwLock, AtomicValue};
+use crate::builder::CyclerBackend;
+use crate::error::CyclerBuildError;
+use crate::generic::{self, GenericCyclerReader, GenericCyclerWriter};
+use crate::slot::Slot;
+use crate::slot_storage::Contiguous;
+use crate::slot_table::MAX_SLOTS;
+use crate::sync::AtomicU32;
+use crate::{EnsureSend, EnsureSync};
 
-pub use builder::{build_multiple_reader, build_single_reader};
-pub use reader::AtomicCyclerReader;
-pub use writer::AtomicCyclerWriter;
+/// The counter of the lock around each slot.
+/// Every reader and the writer can read the same slot at once so it must count up to `MAX_SLOTS - 1` readers.
+/// The lock is padded to the alignment of the slot anyway so this is no bigger than a `u8` counter.
+type SlotCounter = AtomicU32;
+const _: () = assert!(SlotCounter::MAX_READERS >= MAX_SLOTS - 1, "AtomicCycler slot counter is too small for the amount of slots");
 
-use std::sync::atomic::AtomicU8;
-use crate::{EnsureSend, EnsureSync};
-use crate::atomic_rw_lock::AtomicRwLock;
-use std::sync::Arc;
+/// The writer to an `AtomicCycler`
+pub type AtomicCyclerWriter<T> = GenericCyclerWriter<T, Contiguous<AtomicRwLock<Slot<T>, SlotCounter>>>;
+/// The reader for an `AtomicCycler`
+pub type AtomicCyclerReader<T> = GenericCyclerReader<T, Contiguous<AtomicRwLock<Slot<T>, SlotCounter>>>;
 
-#[derive(Debug)]
-struct AtomicCycler<T> {
-    data_slots: Box<[Arc<AtomicRwLock<T, AtomicU8>>]>,
-    most_up_to_date: AtomicU8,
+/// Selects the `AtomicCycler` in `CyclerBuilder`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AtomicBackend;
+impl CyclerBackend for AtomicBackend {
+    type Storage<T> = Contiguous<AtomicRwLock<Slot<T>, SlotCounter>>;
 }
-impl<T> AtomicCycler<T> {
-    const fn num_readers(&self) -> usize {
-        self.data_slots.len() - 2
-    }
+
+impl<T> EnsureSend for AtomicCyclerWriter<T> where T: Send + Sync {}
+impl<T> EnsureSync for AtomicCyclerWriter<T> where T: Send + Sync {}
+impl<T> EnsureSend for AtomicCyclerReader<T> where T: Send + Sync {}
+impl<T> EnsureSync for AtomicCyclerReader<T> where T: Send + Sync {}
+
+/// Creates a single reader AtomicCycler using `values` as the initial values for the slots.
+pub fn build_single_reader<T>(values: [T; 3]) -> (AtomicCyclerWriter<T>, AtomicCyclerReader<T>) {
+    generic::build_single_reader(values)
+}
+
+/// Creates a multi reader AtomicCycler, the amount of readers being `initial_values.len() - 2`.
+pub fn build_multiple_reader<T>(initial_values: Vec<T>) -> (AtomicCyclerWriter<T>, Vec<AtomicCyclerReader<T>>) {
+    generic::build_multiple_reader(initial_values)
+}
+
+/// Creates a single reader AtomicCycler using `values` as the initial values for the slots.
+pub fn try_build_single_reader<T>(values: [T; 3]) -> Result<(AtomicCyclerWriter<T>, AtomicCyclerReader<T>), CyclerBuildError> {
+    generic::try_build_single_reader(values)
+}
+
+/// Creates a multi reader AtomicCycler, the amount of readers being `initial_values.len() - 2`.
+/// Fails if there are fewer than 3 initial values or more than the cycler can have slots.
+pub fn try_build_multiple_reader<T>(initial_values: Vec<T>) -> Result<(AtomicCyclerWriter<T>, Vec<AtomicCyclerReader<T>>), CyclerBuildError> {
+    generic::try_build_multiple_reader(initial_values)
 }
-impl<T> EnsureSend for AtomicCycler<T> where T: Send {}
-impl<T> EnsureSync for AtomicCycler<T> where T: Send + Sync {}
 
 #[cfg(test)]
 mod test {
-    use crate::atomic_cycler::build_single_reader;
-    use crate::test::TestData;
-    use std::sync::atomic::Ordering;
-    use crate::{WriteAccess, ReadAccess, CyclerWriterDefault, CyclerReader};
+    use crate::atomic_cycler::{AtomicBackend, SlotCounter};
+    use crate::atomic_rw_lock::AtomicRwLock;
+    use crate::cache_padded::CACHE_LINE;
+    use crate::testing::run_backend_suite;
+    use crate::{CyclerBuilder, Slot};
+    use std::mem::{align_of, size_of};
+    use std::sync::Arc;
+
+    #[test]
+    fn suite_test() {
+        run_backend_suite::<AtomicBackend>();
+    }
+
+    #[test]
+    fn layout_test() {
+        type Lock = AtomicRwLock<Slot<u8>, SlotCounter>;
+
+        let (writer, mut readers) = CyclerBuilder::new().backend::<AtomicBackend>().readers(3).build_default::<u8>().unwrap();
+        // The built slots are next to each other in one allocation, a cache line or more apart.
+        let address = |index: usize| &writer.cycler.data_slots[index] as *const Lock as usize;
+        let stride = address(1) - address(0);
+        assert!(stride >= size_of::<Lock>() && stride < size_of::<Lock>() + align_of::<Lock>() * 2);
+        for index in 1..5 {
+            assert_eq!(address(index) - address(index - 1), stride);
+        }
+        assert!(stride >= CACHE_LINE);
+        // A slot added later has its own allocation.
+        readers.push(readers[0].try_clone().unwrap());
+        assert_eq!(writer.cycler.data_slots.len(), 6);
+        assert_ne!(address(5).abs_diff(address(4)), stride);
+
+        // Every guard borrows its slot, the cycler is the only `Arc`.
+        assert_eq!(Arc::strong_count(&writer.cycler), 1 + readers.len());
+    }
+}
+
+/// Model checks of publishing and reading, run with `RUSTFLAGS="--cfg loom" cargo test --release --lib loom_`.
+#[cfg(all(test, loom))]
+mod loom_test {
+    use crate::atomic_cycler::{build_multiple_reader, build_single_reader};
+    use crate::testing::TestData;
+    use crate::{CyclerReader, CyclerWriterDefault, ReadAccess};
+    use loom::thread;
+
+    /// Publishes `versions` versions from another thread, setting `test1` to the version.
+    fn spawn_writer<W>(mut writer: W, versions: u64) -> thread::JoinHandle<W> where W: CyclerWriterDefault<TestData> + Send + 'static {
+        thread::spawn(move || {
+            for version in 1..=versions {
+                writer.write_data_mut().test1 = version as usize;
+                assert_eq!(writer.write_next(), version);
+            }
+            writer
+        })
+    }
+
+    /// Reads the latest block `reads` times checking the version never goes backwards and matches the block.
+    fn check_reads<R>(reader: &mut R, reads: usize) where R: CyclerReader<TestData> {
+        let mut last_version = reader.version();
+        for _ in 0..reads {
+            reader.read_latest();
+            assert!(reader.version() >= last_version);
+            assert_eq!(reader.read_data().test1 as u64, reader.version());
+            last_version = reader.version();
+        }
+    }
+
+    /// Checks every interleaving with at most `preemptions` preemptions, unbounded ones take too long once the writer wraps around the slots.
+    fn model(preemptions: usize, f: impl Fn() + Sync + Send + 'static) {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(preemptions);
+        builder.check(f);
+    }
+
+    #[test]
+    fn loom_publish_test() {
+        model(4, || {
+            let (writer, mut reader) = build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+            let writer = spawn_writer(writer, 3);
+            check_reads(&mut reader, 2);
+            let writer = writer.join().unwrap();
+            assert!(reader.read_latest() || reader.version() == 3);
+            assert_eq!(reader.read_data().test1, 3);
+            drop(writer);
+        });
+    }
 
     #[test]
-    fn default_test() {
-        let (mut writer, mut reader) =
-            build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
-        assert_eq!(writer.currently_writing, 1);
-        assert_eq!(writer.cycler.data_slots.len(), 3);
-        assert_eq!(writer.cycler.num_readers(), 1);
-        assert_eq!(writer.cycler.most_up_to_date.load(Ordering::SeqCst), 0);
-        let new_data = TestData { test1: 100, test2: "Test2".to_string(), test3: Box::new(1002) };
-        writer.write_data_mut().clone_from(&new_data);
-        assert_eq!(*reader.read_data(), TestData::default());
-        writer.write_next();
-        assert_eq!(writer.cycler.most_up_to_date.load(Ordering::SeqCst), 1);
-        assert_eq!(writer.currently_writing, 2);
-        assert_eq!(*writer.write_data(), new_data);
-        assert_eq!(*reader.read_data(), TestData::default());
-        reader.read_latest();
-        assert_eq!(*reader.read_data(), new_data);
-        reader.read_latest();
-        assert_eq!(*reader.read_data(), new_data);
+    fn loom_multiple_reader_test() {
+        model(4, || {
+            let (writer, mut readers) = build_multiple_reader(vec![TestData::default(); 4]);
+            let writer = spawn_writer(writer, 2);
+            let mut other = readers.pop().unwrap();
+            let other = thread::spawn(move || check_reads(&mut other, 1));
+            check_reads(&mut readers[0], 1);
+            other.join().unwrap();
+            writer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_drop_test() {
+        loom::model(|| {
+            // The writer is dropped while the reader is reading.
+            let (writer, mut reader) = build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+            let writer = spawn_writer(writer, 1);
+            check_reads(&mut reader, 1);
+            drop(writer.join().unwrap());
+            reader.read_latest();
+            assert_eq!(reader.version(), 1);
+
+            // The reader is dropped while the writer is publishing.
+            let (writer, mut reader) = build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+            let writer = spawn_writer(writer, 2);
+            thread::spawn(move || {
+                check_reads(&mut reader, 1);
+                drop(reader);
+            }).join().unwrap();
+            let writer = writer.join().unwrap();
+            assert_eq!(writer.cycler.num_readers(), 0);
+            for slot in (0..3).filter(|slot| *slot != writer.currently_writing) {
+                assert!(writer.cycler.data_slots[slot].try_write().is_some());
+            }
+        });
     }
 }
@@ -1,68 +1,118 @@
-#![allow(dead_code)]
-//! Allowing dead code here because this may be broken out into its own crate later.
+//! A reader/writer lock built on a single atomic counter, used for the slots of `AtomicCycler` and usable on its own.
+//! The counter stores 1 + the number of readers, or 0 while write locked, so the `try_*` functions are a single compare exchange.
+//! The blocking functions spin with backoff before parking the thread until the lock is released.
 //!
-use std::cell::UnsafeCell;
-use std::ops::{Add, Deref, DerefMut};
-use std::sync::atomic::{AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+//! Guards are `Send` and `Sync` whenever the lock is `Sync`, unlocking from another thread is fine as the lock is only an atomic.
+//!
+//! Locking is an `Acquire` compare exchange of the counter and unlocking a `SeqCst` read-modify-write of it, so everything done under a lock happens before the next lock that reads the unlock.
+//! Unlocking could release with `Release` alone but is `SeqCst` so it is in a single total order with a parking thread setting `parked` then fencing.
+//! Either the unlock comes first and the parking thread's next check sees it, or the flag comes first and the unlocking thread sees it and wakes the parked thread.
+//! A `SeqCst` read-modify-write costs the same as a `Release` one on x86 and ARMv8.
+use std::cell::Cell;
+use std::ops::{Add, Deref, DerefMut, Sub};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::sync::{fence, AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, UnsafeCell};
 use crate::{EnsureSend, EnsureSync};
-use std::fmt::Debug;
+use std::fmt::{Debug, Formatter};
+use parking_lot_core::{ParkResult, SpinWait, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
 
+/// Read guard holding an `Arc` of the lock
 pub type AtomicArcReader<T, A> = AtomicRwLockReader<T, A, Arc<AtomicRwLock<T, A>>>;
+/// Read guard borrowing the lock
 pub type AtomicRefReader<'a, T, A> = AtomicRwLockReader<T, A, &'a AtomicRwLock<T, A>>;
+/// Write guard holding an `Arc` of the lock
 pub type AtomicArcWriter<T, A> = AtomicRwLockWriter<T, A, Arc<AtomicRwLock<T, A>>>;
+/// Write guard borrowing the lock
 pub type AtomicRefWriter<'a, T, A> = AtomicRwLockWriter<T, A, &'a AtomicRwLock<T, A>>;
+/// Upgradable read guard holding an `Arc` of the lock
+pub type AtomicArcUpgradableReader<T, A> = AtomicRwLockUpgradableReader<T, A, Arc<AtomicRwLock<T, A>>>;
+/// Upgradable read guard borrowing the lock
+pub type AtomicRefUpgradableReader<'a, T, A> = AtomicRwLockUpgradableReader<T, A, &'a AtomicRwLock<T, A>>;
+
+/// The outcome of one attempt to lock in `lock_blocking`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Attempt{
+    Locked,
+    Failed,
+    /// Failed after briefly holding the upgradable flag, threads that parked on it must be woken.
+    RolledBack,
+}
+impl From<bool> for Attempt{
+    #[inline]
+    fn from(locked: bool) -> Self{
+        if locked{ Attempt::Locked } else{ Attempt::Failed }
+    }
+}
 
-#[derive(Debug)]
+/// A reader/writer lock whose state is a single atomic of type `A`.
+/// Smaller atomics make the lock smaller but limit how many readers can hold it at once to `A::MAX_READERS`.
+/// Once that many readers hold it, reading fails or blocks like it does while write locked.
 pub struct AtomicRwLock<T, A> {
     /// Atomic that stores 1 + num readers, or 0 if writing
     count: A,
+    /// Set while an upgradable reader holds the lock, there can only be one.
+    upgradable: AtomicBool,
+    /// Set while a thread may be parked waiting on this lock.
+    parked: AtomicBool,
     data: UnsafeCell<T>,
 }
 impl<T, A> EnsureSend for AtomicRwLock<T, A> where T: Send, A: Send, {}
-unsafe impl<T, A> Sync for AtomicRwLock<T, A> where T: Send, A: Sync, {}
+/// Readers on different threads share `&T` so `T` must be `Sync` as well as `Send`.
+unsafe impl<T, A> Sync for AtomicRwLock<T, A> where T: Send + Sync, A: Sync, {}
 impl<T> AtomicRwLock<T, AtomicU8>{
+    /// Creates a lock with a `u8` counter, allowing up to 254 readers at once.
     pub fn new_u8(value: T) -> Self{
         Self::new(value)
     }
 }
 impl<T> AtomicRwLock<T, AtomicU16>{
+    /// Creates a lock with a `u16` counter, allowing up to 65534 readers at once.
     pub fn new_u16(value: T) -> Self{
         Self::new(value)
     }
 }
 impl<T> AtomicRwLock<T, AtomicU32>{
+    /// Creates a lock with a `u32` counter.
     pub fn new_u32(value: T) -> Self{
         Self::new(value)
     }
 }
 impl<T> AtomicRwLock<T, AtomicU64>{
+    /// Creates a lock with a `u64` counter.
     pub fn new_u64(value: T) -> Self{
         Self::new(value)
     }
 }
 impl<T> AtomicRwLock<T, AtomicUsize>{
+    /// Creates a lock with a `usize` counter.
     pub fn new_usize(value: T) -> Self{
         Self::new(value)
     }
 }
 impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
+    /// Creates an unlocked lock holding `value`.
     pub fn new(value: T) -> Self {
-        Self { count: A::new(A::ONE), data: UnsafeCell::new(value) }
+        Self { count: A::new(A::ONE), upgradable: AtomicBool::new(false), parked: AtomicBool::new(false), data: UnsafeCell::new(value) }
     }
 
+    /// Consumes the lock returning the value.
     pub fn into_inner(self) -> T{
         self.data.into_inner()
     }
+    /// Gets the value mutably, no locking is needed as the borrow is exclusive.
     pub fn get_mut(&mut self) -> &mut T{
-        self.data.get_mut()
+        // Safety: the borrow of the lock is exclusive so there are no guards.
+        self.data.with_mut(|data| unsafe{ &mut *data })
     }
 
+    /// Fails if write locked or if the count is saturated, the next reader would wrap it to 0.
     fn lock_read(&self) -> bool{
         let mut prev_readers = self.count.load(Ordering::Relaxed);
         loop {
-            if prev_readers != A::ZERO {
-                match self.count.compare_exchange_weak(prev_readers, prev_readers + A::ONE, Ordering::SeqCst, Ordering::SeqCst) {
+            if prev_readers != A::ZERO && prev_readers != A::MAX {
+                match self.count.compare_exchange_weak(prev_readers, prev_readers + A::ONE, Ordering::Acquire, Ordering::Relaxed) {
                     Ok(_) => return true,
                     Err(new_val) => prev_readers = new_val,
                 }
@@ -76,7 +126,7 @@ impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
         let mut prev_readers = self.count.load(Ordering::Relaxed);
         loop{
             if prev_readers == A::ONE{
-                match self.count.compare_exchange_weak(A::ONE, A::ZERO, Ordering::SeqCst, Ordering::SeqCst){
+                match self.count.compare_exchange_weak(A::ONE, A::ZERO, Ordering::Acquire, Ordering::Relaxed){
                     Ok(_) => return true,
                     Err(new_val) => prev_readers = new_val,
                 }
@@ -87,6 +137,114 @@ impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
         }
     }
 
+    /// Takes the upgradable flag then read locks, rolling the flag back without waking anyone if the read lock fails.
+    /// Threads may have parked on the flag while it was briefly held, the caller must wake them outside of `park` after a roll back.
+    fn try_lock_upgradable(&self) -> Attempt{
+        if self.upgradable.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err(){
+            return Attempt::Failed;
+        }
+        if self.lock_read(){
+            Attempt::Locked
+        }
+        else{
+            self.upgradable.store(false, Ordering::SeqCst);
+            Attempt::RolledBack
+        }
+    }
+
+    fn lock_upgradable(&self) -> bool{
+        match self.try_lock_upgradable(){
+            Attempt::Locked => true,
+            Attempt::Failed => false,
+            Attempt::RolledBack => {
+                self.unpark();
+                false
+            }
+        }
+    }
+
+    /// Turns the read lock of the upgradable reader into the write lock, only possible while it is the only reader.
+    fn lock_upgrade(&self) -> bool{
+        if self.count.compare_exchange(A::ONE + A::ONE, A::ZERO, Ordering::Acquire, Ordering::Relaxed).is_ok(){
+            self.upgradable.store(false, Ordering::SeqCst);
+            true
+        }
+        else{
+            false
+        }
+    }
+
+    #[inline]
+    fn park_key(&self) -> usize{
+        self as *const Self as usize
+    }
+
+    /// Calls `try_lock` until it succeeds or `deadline` passes, spinning with backoff then parking between attempts.
+    /// Threads are woken after an attempt rolls back, never inside `park` where the bucket lock `unpark_all` takes is held.
+    fn lock_blocking(&self, try_lock: impl Fn() -> Attempt, deadline: Option<Instant>) -> bool{
+        let mut spin = SpinWait::new();
+        loop{
+            match try_lock(){
+                Attempt::Locked => return true,
+                Attempt::Failed => {},
+                Attempt::RolledBack => self.unpark(),
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline){
+                return false;
+            }
+            // Loom can't see threads parked by `parking_lot_core` so they would deadlock the model.
+            if cfg!(loom){
+                crate::sync::spin_loop();
+                continue;
+            }
+            if spin.spin(){
+                continue;
+            }
+            // Set and fenced before the lock is checked again in `validate` so an unlock either sees it or is seen by `validate`.
+            // The relaxed loads in `try_lock` would otherwise be free to miss an unlock that missed the flag.
+            self.parked.store(true, Ordering::SeqCst);
+            fence(Ordering::SeqCst);
+            let acquired = Cell::new(false);
+            let rolled_back = Cell::new(false);
+            let validate = ||{
+                match try_lock(){
+                    Attempt::Locked => {
+                        acquired.set(true);
+                        false
+                    },
+                    // Not parked so the threads that may have parked on the roll back are woken below.
+                    Attempt::RolledBack => {
+                        rolled_back.set(true);
+                        false
+                    },
+                    // Cleared if an unlock happened since it was set, the thread would never be woken.
+                    Attempt::Failed => self.parked.load(Ordering::SeqCst),
+                }
+            };
+            // Safety: the key is the address of this lock which can't move while borrowed and the callbacks don't call into `parking_lot_core`.
+            let result = unsafe{ parking_lot_core::park(self.park_key(), validate, ||{}, |_, _|{}, DEFAULT_PARK_TOKEN, deadline) };
+            if acquired.get(){
+                return true;
+            }
+            if rolled_back.get(){
+                self.unpark();
+            }
+            if result == ParkResult::Unparked(DEFAULT_UNPARK_TOKEN){
+                spin.reset();
+            }
+        }
+    }
+
+    /// Wakes every parked thread, called after every unlock.
+    #[inline]
+    fn unpark(&self){
+        if self.parked.load(Ordering::SeqCst) && self.parked.swap(false, Ordering::SeqCst){
+            // Safety: the key is the address of this lock.
+            unsafe{ parking_lot_core::unpark_all(self.park_key(), DEFAULT_UNPARK_TOKEN); }
+        }
+    }
+
+    /// Tries to read lock, returning a guard holding a clone of the `Arc`.
     pub fn try_read_static(self: &Arc<Self>) -> Option<AtomicArcReader<T, A>> {
         if self.lock_read(){
             Some(AtomicArcReader::new(self.clone()))
@@ -96,6 +254,7 @@ impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
         }
     }
 
+    /// Tries to write lock, returning a guard holding a clone of the `Arc`.
     pub fn try_write_static(self: &Arc<Self>) -> Option<AtomicArcWriter<T, A>> {
         if self.lock_write(){
             Some(AtomicArcWriter::new(self.clone()))
@@ -105,6 +264,41 @@ impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
         }
     }
 
+    /// Tries to read lock the lock `owner` derefs to, returning a guard holding `owner`.
+    /// Not public as `owner` must deref to the same lock every time, which `Deref` doesn't promise.
+    pub(crate) fn try_read_owned<L>(owner: L) -> Option<AtomicRwLockReader<T, A, L>> where L: Deref<Target = Self> {
+        if owner.lock_read(){
+            Some(AtomicRwLockReader::new(owner))
+        }
+        else{
+            None
+        }
+    }
+
+    /// Tries to write lock the lock `owner` derefs to, returning a guard holding `owner`.
+    /// Not public as `owner` must deref to the same lock every time, which `Deref` doesn't promise.
+    pub(crate) fn try_write_owned<L>(owner: L) -> Option<AtomicRwLockWriter<T, A, L>> where L: Deref<Target = Self> {
+        if owner.lock_write(){
+            Some(AtomicRwLockWriter::new(owner))
+        }
+        else{
+            None
+        }
+    }
+
+    /// Read locks, blocking until there is no writer, returning a guard holding a clone of the `Arc`.
+    pub fn read_static(self: &Arc<Self>) -> AtomicArcReader<T, A> {
+        self.lock_blocking(|| self.lock_read().into(), None);
+        AtomicArcReader::new(self.clone())
+    }
+
+    /// Write locks, blocking until there are no readers or writer, returning a guard holding a clone of the `Arc`.
+    pub fn write_static(self: &Arc<Self>) -> AtomicArcWriter<T, A> {
+        self.lock_blocking(|| self.lock_write().into(), None);
+        AtomicArcWriter::new(self.clone())
+    }
+
+    /// Tries to read lock, failing if there is a writer.
     pub fn try_read(&self) -> Option<AtomicRefReader<'_, T, A>>{
         if self.lock_read(){
             Some(AtomicRefReader::new(self))
@@ -114,6 +308,7 @@ impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
         }
     }
 
+    /// Tries to write lock, failing if there are readers or a writer.
     pub fn try_write(&self) -> Option<AtomicRefWriter<'_, T, A>>{
         if self.lock_write(){
             Some(AtomicRefWriter::new(self))
@@ -122,10 +317,86 @@ impl<T, A> AtomicRwLock<T, A> where A: AtomicValue, {
             None
         }
     }
+
+    /// Tries to upgradably read lock, failing if there is a writer or another upgradable reader.
+    pub fn try_upgradable_read(&self) -> Option<AtomicRefUpgradableReader<'_, T, A>>{
+        if self.lock_upgradable(){
+            Some(AtomicRefUpgradableReader::new(self))
+        }
+        else{
+            None
+        }
+    }
+
+    /// Read locks, blocking until there is no writer.
+    pub fn read(&self) -> AtomicRefReader<'_, T, A>{
+        self.lock_blocking(|| self.lock_read().into(), None);
+        AtomicRefReader::new(self)
+    }
+
+    /// Write locks, blocking until there are no readers or writer.
+    /// Readers can keep a writer waiting as long as there is always at least one.
+    pub fn write(&self) -> AtomicRefWriter<'_, T, A>{
+        self.lock_blocking(|| self.lock_write().into(), None);
+        AtomicRefWriter::new(self)
+    }
+
+    /// Upgradably read locks, blocking until there is no writer or other upgradable reader.
+    pub fn upgradable_read(&self) -> AtomicRefUpgradableReader<'_, T, A>{
+        self.lock_blocking(|| self.try_lock_upgradable(), None);
+        AtomicRefUpgradableReader::new(self)
+    }
+
+    /// Read locks, blocking for at most `timeout`.
+    pub fn try_read_for(&self, timeout: Duration) -> Option<AtomicRefReader<'_, T, A>>{
+        if self.lock_blocking(|| self.lock_read().into(), Instant::now().checked_add(timeout)){
+            Some(AtomicRefReader::new(self))
+        }
+        else{
+            None
+        }
+    }
+
+    /// Write locks, blocking for at most `timeout`.
+    pub fn try_write_for(&self, timeout: Duration) -> Option<AtomicRefWriter<'_, T, A>>{
+        if self.lock_blocking(|| self.lock_write().into(), Instant::now().checked_add(timeout)){
+            Some(AtomicRefWriter::new(self))
+        }
+        else{
+            None
+        }
+    }
+
+    /// Upgradably read locks, blocking for at most `timeout`.
+    pub fn try_upgradable_read_for(&self, timeout: Duration) -> Option<AtomicRefUpgradableReader<'_, T, A>>{
+        if self.lock_blocking(|| self.try_lock_upgradable(), Instant::now().checked_add(timeout)){
+            Some(AtomicRefUpgradableReader::new(self))
+        }
+        else{
+            None
+        }
+    }
+}
+impl<T, A> Debug for AtomicRwLock<T, A> where T: Debug, A: AtomicValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let count = self.count.load(Ordering::Relaxed);
+        let mut debug = f.debug_struct("AtomicRwLock");
+        if count == A::ZERO{
+            debug.field("readers", &A::ZERO).field("write_locked", &true).field("data", &format_args!("<locked>"));
+        }
+        else{
+            debug.field("readers", &(count - A::ONE)).field("write_locked", &false);
+            match self.try_read(){
+                Some(reader) => debug.field("data", &&*reader),
+                None => debug.field("data", &format_args!("<locked>")),
+            };
+        }
+        debug.field("upgradable", &self.upgradable.load(Ordering::Relaxed)).finish()
+    }
 }
 impl<T, A> Default for AtomicRwLock<T, A> where T: Default, A: AtomicValue, {
     fn default() -> Self {
-        Self { count: A::new(A::ONE), data: UnsafeCell::default() }
+        Self::new(T::default())
     }
 }
 impl<T, A> From<T> for AtomicRwLock<T, A> where T: Default, A: AtomicValue, {
@@ -134,7 +405,7 @@ impl<T, A> From<T> for AtomicRwLock<T, A> where T: Default, A: AtomicValue, {
     }
 }
 
-#[derive(Debug)]
+/// Shared access to the value of an `AtomicRwLock`, unlocked on drop.
 pub struct AtomicRwLockReader<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
     lock: L,
 }
@@ -147,7 +418,12 @@ impl<T, A, L> Deref for AtomicRwLockReader<T, A, L> where L: Deref<Target=Atomic
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        unsafe { &*self.lock.data.get() }
+        self.lock.data.with(|data| unsafe { &*data })
+    }
+}
+impl<T, A, L> Debug for AtomicRwLockReader<T, A, L> where T: Debug, L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
     }
 }
 impl<T, A, L> Drop for AtomicRwLockReader<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
@@ -161,12 +437,13 @@ impl<T, A, L> Drop for AtomicRwLockReader<T, A, L> where L: Deref<Target=AtomicR
         #[cfg(not(debug_assertions))] {
             self.lock.count.fetch_sub(A::ONE, Ordering::SeqCst);
         }
+        self.lock.unpark();
     }
 }
 impl<T, A, L> EnsureSend for AtomicRwLockReader<T, A, L> where L: Send + Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {}
 impl<T, A, L> EnsureSync for AtomicRwLockReader<T, A, L> where L: Sync + Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {}
 
-#[derive(Debug)]
+/// Exclusive access to the value of an `AtomicRwLock`, unlocked on drop.
 pub struct AtomicRwLockWriter<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
     lock: Option<L>,
 }
@@ -175,53 +452,152 @@ impl<T, A, L> AtomicRwLockWriter<T, A, L> where L: Deref<Target=AtomicRwLock<T,
         Self{ lock: Some(lock) }
     }
 
-    pub fn into_inner(mut self) -> L{
+    fn into_inner(mut self) -> L{
         self.lock.take().unwrap()
     }
+    /// Turns the write lock into a read lock without letting another writer in between.
     pub fn downgrade(self) -> AtomicRwLockReader<T, A, L>{
         self.lock.as_ref().unwrap().count.fetch_add(A::ONE + A::ONE, Ordering::SeqCst);
-        AtomicRwLockReader{ lock: self.into_inner() }
+        let lock = self.into_inner();
+        lock.unpark();
+        AtomicRwLockReader{ lock }
     }
 }
 impl<T, A, L> Deref for AtomicRwLockWriter<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue{
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        unsafe{ &*self.lock.as_ref().unwrap().data.get() }
+        self.lock.as_ref().unwrap().data.with(|data| unsafe{ &*data })
     }
 }
 impl<T, A, L> DerefMut for AtomicRwLockWriter<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue{
     fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe{ &mut *self.lock.as_ref().unwrap().data.get() }
+        self.lock.as_ref().unwrap().data.with_mut(|data| unsafe{ &mut *data })
+    }
+}
+impl<T, A, L> Debug for AtomicRwLockWriter<T, A, L> where T: Debug, L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
     }
 }
 impl<T, A, L> Drop for AtomicRwLockWriter<T, A, L>where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue{
     fn drop(&mut self) {
         if let Some(lock) = &self.lock {
             #[cfg(debug_assertions)] {
-                lock.count.compare_exchange(A::ZERO, A::ONE, Ordering::SeqCst, Ordering::SeqCst).expect("Writer was dropped and lock count was not 0!");
+                lock.count.compare_exchange(A::ZERO, A::ONE, Ordering::SeqCst, Ordering::Relaxed).expect("Writer was dropped and lock count was not 0!");
             }
             #[cfg(not(debug_assertions))] {
                 lock.count.fetch_add(A::ONE, Ordering::SeqCst);
             }
+            lock.unpark();
         }
     }
 }
 impl<T, A, L> EnsureSend for AtomicRwLockWriter<T, A, L>where L: Send + Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue{}
 impl<T, A, L> EnsureSync for AtomicRwLockWriter<T, A, L>where L: Sync + Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue{}
 
+/// Shared access to the value of an `AtomicRwLock` that can be upgraded to exclusive access, unlocked on drop.
+/// Only one upgradable reader can hold a lock at once, so upgrading can't deadlock with another upgrade.
+pub struct AtomicRwLockUpgradableReader<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    lock: Option<L>,
+}
+impl<T, A, L> AtomicRwLockUpgradableReader<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    fn new(lock: L) -> Self {
+        Self { lock: Some(lock) }
+    }
+
+    fn lock(&self) -> &AtomicRwLock<T, A>{
+        self.lock.as_ref().unwrap()
+    }
+
+    /// Tries to upgrade to a write lock, failing if there are other readers.
+    pub fn try_upgrade(mut self) -> Result<AtomicRwLockWriter<T, A, L>, Self>{
+        if self.lock().lock_upgrade(){
+            Ok(AtomicRwLockWriter::new(self.lock.take().unwrap()))
+        }
+        else{
+            Err(self)
+        }
+    }
+
+    /// Upgrades to a write lock, blocking until the other readers have unlocked.
+    pub fn upgrade(mut self) -> AtomicRwLockWriter<T, A, L>{
+        let lock = self.lock();
+        lock.lock_blocking(|| lock.lock_upgrade().into(), None);
+        AtomicRwLockWriter::new(self.lock.take().unwrap())
+    }
+
+    /// Upgrades to a write lock, blocking for at most `timeout`.
+    pub fn try_upgrade_for(mut self, timeout: Duration) -> Result<AtomicRwLockWriter<T, A, L>, Self>{
+        let lock = self.lock();
+        if lock.lock_blocking(|| lock.lock_upgrade().into(), Instant::now().checked_add(timeout)){
+            Ok(AtomicRwLockWriter::new(self.lock.take().unwrap()))
+        }
+        else{
+            Err(self)
+        }
+    }
+
+    /// Turns into a plain read lock, letting another upgradable reader in.
+    pub fn downgrade(mut self) -> AtomicRwLockReader<T, A, L>{
+        let lock = self.lock.take().unwrap();
+        lock.upgradable.store(false, Ordering::SeqCst);
+        lock.unpark();
+        AtomicRwLockReader::new(lock)
+    }
+}
+impl<T, A, L> Deref for AtomicRwLockUpgradableReader<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.lock().data.with(|data| unsafe { &*data })
+    }
+}
+impl<T, A, L> Debug for AtomicRwLockUpgradableReader<T, A, L> where T: Debug, L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+impl<T, A, L> Drop for AtomicRwLockUpgradableReader<T, A, L> where L: Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {
+    fn drop(&mut self) {
+        if let Some(lock) = self.lock.take(){
+            lock.upgradable.store(false, Ordering::SeqCst);
+            // Unlocks the read lock and wakes waiters.
+            drop(AtomicRwLockReader::new(lock));
+        }
+    }
+}
+impl<T, A, L> EnsureSend for AtomicRwLockUpgradableReader<T, A, L> where L: Send + Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {}
+impl<T, A, L> EnsureSync for AtomicRwLockUpgradableReader<T, A, L> where L: Sync + Deref<Target=AtomicRwLock<T, A>>, A: AtomicValue {}
+
 /// This trait is unsafe because is must be implemented on an atomic.
 /// If implementation is not correct this will create unsafe situations.
+///
+/// # Safety
+/// Every operation must be performed atomically with the given orderings.
 pub unsafe trait AtomicValue {
-    type Stored: Copy + PartialOrd + Ord + PartialEq + Eq + Add<Output=Self::Stored> + Debug;
+    /// The integer stored in the atomic.
+    type Stored: Copy + PartialOrd + Ord + PartialEq + Eq + Add<Output=Self::Stored> + Sub<Output=Self::Stored> + Debug;
+    /// Zero of `Stored`
     const ZERO: Self::Stored;
+    /// One of `Stored`
     const ONE: Self::Stored;
+    /// The largest value of `Stored`
+    const MAX: Self::Stored;
+    /// The most readers a lock using this atomic can have at once, one less than `MAX` as the count is offset by one.
+    const MAX_READERS: usize;
 
+    /// Creates the atomic holding `val`.
     fn new(val: Self::Stored) -> Self where Self: Sized;
+    /// Atomically loads the value.
     fn load(&self, ordering: Ordering) -> Self::Stored;
+    /// Atomically replaces the value with `new` if it is `current`.
     fn compare_exchange(&self, current: Self::Stored, new: Self::Stored, success: Ordering, failure: Ordering, ) -> Result<Self::Stored, Self::Stored>;
+    /// Atomically replaces the value with `new` if it is `current`, may spuriously fail.
     fn compare_exchange_weak(&self, current: Self::Stored, new: Self::Stored, success: Ordering, failure: Ordering, ) -> Result<Self::Stored, Self::Stored>;
+    /// Atomically adds `val` returning the previous value.
     fn fetch_add(&self, val: Self::Stored, ordering: Ordering) -> Self::Stored;
+    /// Atomically subtracts `val` returning the previous value.
     fn fetch_sub(&self, val: Self::Stored, ordering: Ordering) -> Self::Stored;
 }
 macro_rules! impl_atomic_value {
@@ -230,6 +606,8 @@ macro_rules! impl_atomic_value {
             type Stored = $stored;
             const ZERO: Self::Stored = 0 as $stored;
             const ONE: Self::Stored = 1 as $stored;
+            const MAX: Self::Stored = <$stored>::MAX;
+            const MAX_READERS: usize = if (<$stored>::MAX - 1) as u128 > usize::MAX as u128 { usize::MAX } else { (<$stored>::MAX - 1) as usize };
 
             #[inline]
             fn new(val: Self::Stored) -> Self where Self: Sized{
@@ -271,9 +649,13 @@ impl_atomic_value!(AtomicUsize, usize);
 
 #[cfg(test)]
 mod test{
-    use crate::atomic_rw_lock::{AtomicRwLock, AtomicValue};
+    use crate::atomic_rw_lock::{AtomicArcReader, AtomicArcWriter, AtomicRefReader, AtomicRefUpgradableReader, AtomicRefWriter, AtomicRwLock, AtomicValue};
+    use crate::sync::{AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize};
     use std::sync::atomic::Ordering;
     use std::ops::{DerefMut, Deref};
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
 
     fn single_thread_test<A: AtomicValue>(lock: AtomicRwLock<String, A>){
         assert_eq!(A::ONE, lock.count.load(Ordering::SeqCst));
@@ -332,4 +714,273 @@ mod test{
     fn test_usize(){
         single_thread_test(AtomicRwLock::new_usize("".to_string()));
     }
+
+    /// Fills the lock with readers up to `A::MAX_READERS`, then checks reading fails or waits until one unlocks.
+    /// `held` readers are locked for real, the rest are added to the count directly as the wider counters can't be filled.
+    fn saturation_test<A>(held: usize) where A: AtomicValue + Send + Sync + 'static{
+        let lock = Arc::new(AtomicRwLock::<usize, A>::new(0));
+        let mut count = A::MAX;
+        for _ in 0..held{
+            count = count - A::ONE;
+        }
+        if count != A::ONE{
+            lock.count.compare_exchange(A::ONE, count, Ordering::SeqCst, Ordering::SeqCst).unwrap();
+        }
+        let mut readers: Vec<_> = (0..held).map(|_| lock.try_read_static().expect("Could not read lock below the limit")).collect();
+        assert_eq!(lock.count.load(Ordering::SeqCst), A::MAX);
+        assert!(lock.try_read().is_none());
+        assert!(lock.try_upgradable_read().is_none());
+        assert!(lock.try_write().is_none());
+        assert!(lock.try_read_for(Duration::from_millis(10)).is_none());
+
+        let waiting = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.read())
+        };
+        thread::sleep(Duration::from_millis(20));
+        assert!(!waiting.is_finished());
+        readers.pop();
+        assert_eq!(waiting.join().unwrap(), 0);
+        let reader = lock.try_read().expect("Could not read lock after a reader unlocked");
+        assert_eq!(lock.count.load(Ordering::SeqCst), A::MAX);
+        assert!(lock.try_read().is_none());
+        drop(reader);
+    }
+
+    #[test]
+    fn saturation_u8_test(){
+        assert_eq!(AtomicU8::MAX_READERS, 254);
+        saturation_test::<AtomicU8>(254);
+    }
+    #[test]
+    fn saturation_u16_test(){
+        assert_eq!(AtomicU16::MAX_READERS, 65534);
+        saturation_test::<AtomicU16>(65534);
+    }
+    #[test]
+    fn saturation_u32_test(){
+        assert_eq!(AtomicU32::MAX_READERS as u64, u32::MAX as u64 - 1);
+        saturation_test::<AtomicU32>(1);
+    }
+    #[test]
+    fn saturation_u64_test(){
+        assert_eq!(AtomicU64::MAX_READERS as u128, (u64::MAX - 1).min(usize::MAX as u64) as u128);
+        saturation_test::<AtomicU64>(1);
+    }
+    #[test]
+    fn saturation_usize_test(){
+        assert_eq!(AtomicUsize::MAX_READERS, usize::MAX - 1);
+        saturation_test::<AtomicUsize>(1);
+    }
+
+    #[test]
+    fn upgradable_test(){
+        let lock = AtomicRwLock::new_u8(1);
+        let upgradable = lock.try_upgradable_read().unwrap();
+        assert!(lock.try_upgradable_read().is_none());
+        assert!(lock.try_write().is_none());
+        let reader = lock.try_read().unwrap();
+        let upgradable = upgradable.try_upgrade().unwrap_err();
+        drop(reader);
+        let mut writer = upgradable.try_upgrade().unwrap();
+        *writer = 2;
+        assert!(lock.try_read().is_none());
+        assert!(lock.try_upgradable_read().is_none());
+        drop(writer);
+
+        let upgradable = lock.upgradable_read();
+        assert_eq!(*upgradable, 2);
+        let reader = upgradable.downgrade();
+        let upgradable = lock.try_upgradable_read().unwrap();
+        assert!(upgradable.try_upgrade_for(Duration::from_millis(10)).is_err());
+        drop(reader);
+        assert_eq!(lock.count.load(Ordering::SeqCst), 1);
+        assert!(!lock.upgradable.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn blocking_test(){
+        let lock = Arc::new(AtomicRwLock::new_u8(0));
+        let writer = lock.write();
+        assert!(lock.try_read_for(Duration::from_millis(10)).is_none());
+        assert!(lock.try_write_for(Duration::from_millis(10)).is_none());
+        let handles: Vec<_> = (0..4).map(|_|{
+            let lock = lock.clone();
+            thread::spawn(move ||{
+                for _ in 0..100{
+                    *lock.write() += 1;
+                    let upgradable = lock.upgradable_read();
+                    let value = *upgradable;
+                    let mut writer = upgradable.upgrade();
+                    assert_eq!(*writer, value);
+                    *writer += 1;
+                    drop(writer);
+                    assert!(*lock.read() > 0);
+                }
+            })
+        }).collect();
+        // Let the threads park on the write lock before releasing it.
+        thread::sleep(Duration::from_millis(50));
+        drop(writer);
+        for handle in handles{
+            handle.join().unwrap();
+        }
+        assert_eq!(*lock.read(), 800);
+        assert_eq!(*lock.try_read_for(Duration::from_millis(10)).unwrap(), 800);
+        let _writer = lock.write_static();
+        assert!(lock.try_read_static().is_none());
+    }
+
+    /// Upgradable readers blocked behind a writer roll their flag back on every attempt, which must not wake other threads from inside `park`.
+    #[test]
+    fn upgradable_blocked_by_writer_test(){
+        let lock = Arc::new(AtomicRwLock::new_u8(0));
+        let writer = lock.write();
+        let (sender, receiver) = mpsc::channel();
+        let handles: Vec<_> = (0..2).map(|thread|{
+            let lock = lock.clone();
+            let sender = sender.clone();
+            thread::spawn(move ||{
+                let value = if thread == 0{
+                    *lock.upgradable_read()
+                }
+                else{
+                    *lock.try_upgradable_read_for(Duration::from_secs(10)).expect("Timed out behind the writer")
+                };
+                sender.send(value).unwrap();
+            })
+        }).collect();
+        // Let the threads park on the write lock before releasing it.
+        thread::sleep(Duration::from_millis(50));
+        drop(writer);
+        for _ in &handles{
+            assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(0));
+        }
+        for handle in handles{
+            handle.join().unwrap();
+        }
+        assert!(!lock.upgradable.load(Ordering::SeqCst));
+    }
+
+    /// Hands the lock between threads many times, each unlock either wakes a parked thread or is seen by it before parking.
+    /// A lost wake up would hang, a missing acquire or release would let a reader see a half written vector.
+    #[test]
+    fn handoff_stress_test(){
+        let lock = Arc::new(AtomicRwLock::new_u8(Vec::new()));
+        let iterations = if cfg!(miri) { 20 } else { 2_000 };
+        let handles: Vec<_> = (0..4).map(|thread|{
+            let lock = lock.clone();
+            thread::spawn(move ||{
+                for iteration in 0..iterations{
+                    if (thread + iteration) % 2 == 0{
+                        let mut writer = lock.write();
+                        let len = writer.len();
+                        writer.push(len);
+                    }
+                    else{
+                        let reader = lock.read();
+                        assert!(reader.iter().enumerate().all(|(index, value)| index == *value));
+                    }
+                }
+            })
+        }).collect();
+        for handle in handles{
+            handle.join().unwrap();
+        }
+        assert_eq!(lock.read().len(), iterations * 2);
+    }
+
+    #[test]
+    fn debug_test(){
+        let lock = AtomicRwLock::new_u8(5);
+        assert_eq!(format!("{:?}", lock), "AtomicRwLock { readers: 0, write_locked: false, data: 5, upgradable: false }");
+        let reader = lock.read();
+        assert_eq!(format!("{:?}", reader), "5");
+        assert_eq!(format!("{:?}", lock), "AtomicRwLock { readers: 1, write_locked: false, data: 5, upgradable: false }");
+        drop(reader);
+        let _writer = lock.write();
+        assert_eq!(format!("{:?}", lock), "AtomicRwLock { readers: 0, write_locked: true, data: <locked>, upgradable: false }");
+    }
+
+    #[test]
+    fn send_sync_test(){
+        fn send_sync<T: Send + Sync>(){}
+        send_sync::<AtomicRwLock<String, AtomicU8>>();
+        send_sync::<AtomicRefReader<'static, String, AtomicU8>>();
+        send_sync::<AtomicRefWriter<'static, String, AtomicU8>>();
+        send_sync::<AtomicRefUpgradableReader<'static, String, AtomicU8>>();
+        send_sync::<AtomicArcReader<String, AtomicU8>>();
+        send_sync::<AtomicArcWriter<String, AtomicU8>>();
+    }
+}
+
+/// Model checks of the lock, run with `RUSTFLAGS="--cfg loom" cargo test --release --lib loom_`.
+#[cfg(all(test, loom))]
+mod loom_test{
+    use crate::atomic_rw_lock::AtomicRwLock;
+    use crate::sync::AtomicU8;
+    use loom::thread;
+    use std::sync::Arc;
+
+    #[test]
+    fn loom_read_write_test(){
+        loom::model(||{
+            let lock = Arc::new(AtomicRwLock::<usize, AtomicU8>::new(0));
+            let writer = {
+                let lock = lock.clone();
+                thread::spawn(move ||{
+                    if let Some(mut writer) = lock.try_write_static(){
+                        *writer += 1;
+                    }
+                })
+            };
+            // Loom's cell fails the model if the read overlaps the write.
+            let read = lock.try_read_static().map(|reader| *reader);
+            assert!(matches!(read, None | Some(0) | Some(1)));
+            writer.join().unwrap();
+            assert!(lock.try_write().is_some());
+        });
+    }
+
+    #[test]
+    fn loom_downgrade_test(){
+        loom::model(||{
+            let lock = Arc::new(AtomicRwLock::<usize, AtomicU8>::new(0));
+            let threads: Vec<_> = (1..=2).map(|value|{
+                let lock = lock.clone();
+                thread::spawn(move ||{
+                    if let Some(mut writer) = lock.try_write_static(){
+                        *writer = value;
+                        // No other writer can get in between.
+                        let reader = writer.downgrade();
+                        assert_eq!(*reader, value);
+                        assert!(lock.try_write().is_none());
+                    }
+                })
+            }).collect();
+            for thread in threads{
+                thread.join().unwrap();
+            }
+            assert_eq!(lock.count.load(std::sync::atomic::Ordering::SeqCst), 1);
+        });
+    }
+
+    #[test]
+    fn loom_upgrade_test(){
+        loom::model(||{
+            let lock = Arc::new(AtomicRwLock::<usize, AtomicU8>::new(0));
+            let upgrader = {
+                let lock = lock.clone();
+                thread::spawn(move ||{
+                    let reader = lock.upgradable_read();
+                    let mut writer = reader.upgrade();
+                    *writer += 1;
+                })
+            };
+            let read = *lock.read();
+            assert!(read <= 1);
+            upgrader.join().unwrap();
+            assert_eq!(*lock.read(), 1);
+        });
+    }
 }
@@ -0,0 +1,173 @@
+//! A configurable builder for the cyclers that lock a slot per reader, `RwLockCycler`, `AtomicCycler` or another generic cycler.
+
+use crate::error::CyclerBuildError;
+use crate::generic::{self, GenericCyclerReader, GenericCyclerWriter};
+use crate::slot_storage::SlotStorage;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+/// A cycler implementation that `CyclerBuilder` can build, selecting how slots are stored and locked.
+/// Implemented by `RwLockBackend` and `AtomicBackend`, other locks can be used by implementing this on a new type.
+/// Only backends of the generic cycler, which stores and locks a slot per reader, can be expressed.
+/// `TripleBufferCycler` and `SeqLockCycler` have no backend and are built with their modules' `build_single_reader`.
+pub trait CyclerBackend {
+    /// The storage of the slots holding a `T`, any `SlotLock` around a `Slot<T>` or `Contiguous`.
+    type Storage<T>: SlotStorage<T>;
+}
+
+/// The writer of a cycler built with backend `B`.
+pub type BackendWriter<T, B> = GenericCyclerWriter<T, <B as CyclerBackend>::Storage<T>>;
+/// The reader of a cycler built with backend `B`.
+pub type BackendReader<T, B> = GenericCyclerReader<T, <B as CyclerBackend>::Storage<T>>;
+/// The writer and readers of a cycler built with backend `B`.
+pub type BuiltCycler<T, B> = (BackendWriter<T, B>, Vec<BackendReader<T, B>>);
+
+/// Builds a cycler with backend `B`, by default `DefaultBackend` which is the `RwLockBackend`.
+/// Any amount of readers builds backend `B`, a single reader builder does not build the `TripleBufferCycler` the single reader DefaultCycler builders return.
+/// Every cycler has a slot for the writer, one per reader and a spare one, extra spare slots can be added with `spare_slots`.
+/// Spare slots let the writer skip more slots being read and are used by readers added later instead of allocating.
+///
+/// ```
+/// use cycler::{CyclerBuilder, RwLockBackend};
+///
+/// let (writer, readers) = CyclerBuilder::new()
+///     .backend::<RwLockBackend>()
+///     .readers(4)
+///     .spare_slots(2)
+///     .build_with(|slot_index| vec![0u8; slot_index])
+///     .unwrap();
+/// assert_eq!(readers.len(), 4);
+/// # drop(writer);
+/// ```
+pub struct CyclerBuilder<B = crate::DefaultBackend> {
+    readers: usize,
+    spare_slots: usize,
+    backend: PhantomData<fn() -> B>,
+}
+impl CyclerBuilder {
+    /// Creates a builder for the `DefaultBackend` with a single reader and no extra spare slots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl<B> CyclerBuilder<B> where B: CyclerBackend {
+    /// Changes the backend to `B2`, keeping the amount of readers and spare slots.
+    pub fn backend<B2>(self) -> CyclerBuilder<B2> where B2: CyclerBackend {
+        CyclerBuilder { readers: self.readers, spare_slots: self.spare_slots, backend: PhantomData }
+    }
+
+    /// Sets the amount of readers built, 1 by default.
+    pub fn readers(mut self, readers: usize) -> Self {
+        self.readers = readers;
+        self
+    }
+
+    /// Sets the amount of spare slots on top of the one every cycler has, 0 by default.
+    pub fn spare_slots(mut self, spare_slots: usize) -> Self {
+        self.spare_slots = spare_slots;
+        self
+    }
+
+    /// The amount of slots the cycler will have.
+    /// Fails if that is fewer than 3 or more than the backend can have.
+    pub fn slots<T>(&self) -> Result<usize, CyclerBuildError> {
+        generic::slots_for_readers::<T, B::Storage<T>>(self.readers, self.spare_slots)
+    }
+
+    /// Builds the cycler with every slot initialized to `T::default()`.
+    pub fn build_default<T>(self) -> Result<BuiltCycler<T, B>, CyclerBuildError> where T: Default {
+        self.build_with(|_| T::default())
+    }
+
+    /// Builds the cycler with every slot initialized to a clone of `value`.
+    pub fn build_cloned<T>(self, value: T) -> Result<BuiltCycler<T, B>, CyclerBuildError> where T: Clone {
+        let slots = self.slots::<T>()?;
+        generic::build_with_readers(vec![value; slots], self.readers)
+    }
+
+    /// Builds the cycler with each slot initialized by calling `init` with its index.
+    /// The readers start reading slot 0 and the writer starts writing slot 1.
+    pub fn build_with<T>(self, init: impl FnMut(usize) -> T) -> Result<BuiltCycler<T, B>, CyclerBuildError> {
+        let slots = self.slots::<T>()?;
+        generic::build_with_readers((0..slots).map(init).collect(), self.readers)
+    }
+}
+impl<B> Default for CyclerBuilder<B> {
+    fn default() -> Self {
+        Self { readers: 1, spare_slots: 0, backend: PhantomData }
+    }
+}
+impl<B> Clone for CyclerBuilder<B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<B> Copy for CyclerBuilder<B> {}
+impl<B> Debug for CyclerBuilder<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CyclerBuilder")
+            .field("backend", &std::any::type_name::<B>())
+            .field("readers", &self.readers)
+            .field("spare_slots", &self.spare_slots)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::builder::CyclerBuilder;
+    use crate::error::CyclerBuildError;
+    use crate::rw_lock_cycler::{RwLockCyclerReader, RwLockCyclerWriter};
+    use crate::testing::TestData;
+    use crate::{CyclerReader, CyclerWriterDefault, ReadAccess, WriteAccess};
+
+    #[test]
+    fn build_test() {
+        let (mut writer, mut readers) = CyclerBuilder::new().readers(3).spare_slots(2).build_with(|slot| TestData { test1: slot, ..TestData::default() }).unwrap();
+        assert_eq!(writer.cycler.data_slots.len(), 7);
+        assert_eq!(writer.cycler.num_readers(), 3);
+        assert_eq!(readers.len(), 3);
+        assert!(readers.iter().all(|reader| reader.read_data().test1 == 0));
+        assert_eq!(writer.write_data().test1, 1);
+        writer.write_next();
+        assert!(readers[0].read_latest());
+        assert_eq!(readers[0].read_data().test1, 1);
+
+        // Readers added later use the spare slots.
+        readers.push(writer.add_reader().unwrap());
+        readers.push(writer.add_reader().unwrap());
+        assert_eq!(writer.cycler.data_slots.len(), 7);
+        assert!(readers[3..].iter().all(|reader| reader.read_data().test1 == 1));
+
+        // The builder returns the same types as the backend functions.
+        let (_writer, _readers): (RwLockCyclerWriter<TestData>, Vec<RwLockCyclerReader<TestData>>) = CyclerBuilder::new().build_default().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "atomic_cycler")]
+    fn atomic_test() {
+        use crate::atomic_cycler::{AtomicBackend, AtomicCyclerReader};
+        use crate::testing::monotonic_stress_test;
+
+        let builder = CyclerBuilder::new().readers(2).backend::<AtomicBackend>();
+        let (writer, readers): (_, Vec<AtomicCyclerReader<TestData>>) = builder.build_cloned(TestData { test1: 0, ..TestData::default() }).unwrap();
+        assert_eq!(writer.cycler.data_slots.len(), 4);
+        monotonic_stress_test(writer, readers, if cfg!(miri) { 10 } else { 1_000 });
+    }
+
+    #[test]
+    fn error_test() {
+        assert_eq!(CyclerBuilder::new().readers(0).build_default::<usize>().unwrap_err(), CyclerBuildError::TooFewSlots { slots: 2 });
+        // No readers is fine as long as there are enough spare slots, readers can be added later.
+        let (writer, readers) = CyclerBuilder::new().readers(0).spare_slots(1).build_default::<TestData>().unwrap();
+        assert!(readers.is_empty());
+        assert_eq!(writer.add_reader().unwrap().version(), 0);
+        assert_eq!(writer.cycler.data_slots.len(), 3);
+
+        let builder = CyclerBuilder::new().readers(usize::MAX - 1);
+        assert!(matches!(builder.slots::<usize>(), Err(CyclerBuildError::TooManyReaders { readers, .. }) if readers == usize::MAX - 1));
+        let builder = CyclerBuilder::new().spare_slots(usize::MAX);
+        assert!(matches!(builder.build_cloned(0).unwrap_err(), CyclerBuildError::TooManySlots { slots: usize::MAX, .. }));
+        assert_eq!(format!("{:?}", CyclerBuilder::new()), "CyclerBuilder { backend: \"cycler::rw_lock_cycler::RwLockBackend\", readers: 1, spare_slots: 0 }");
+    }
+}
@@ -0,0 +1,81 @@
+//! Padding that keeps data written by one thread off the cache lines read or written by others.
+//! x86_64 and aarch64 prefetch cache lines in pairs, so 128 bytes is padded there and 64 elsewhere.
+
+use std::fmt::{Debug, Formatter};
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+/// The size padded to so two values are never on the same cache line or prefetched pair of them.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64"))]
+pub(crate) const CACHE_LINE: usize = 128;
+/// The size padded to so two values are never on the same cache line or prefetched pair of them.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64")))]
+pub(crate) const CACHE_LINE: usize = 64;
+
+/// Aligns and pads a value to `CACHE_LINE`, for values stored next to others inside a struct.
+#[derive(Default)]
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64"), repr(align(128)))]
+#[cfg_attr(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64")), repr(align(64)))]
+pub(crate) struct CachePadded<T>(T);
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for CachePadded<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl<T> Debug for CachePadded<T> where T: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+/// `CACHE_LINE` bytes placed at the end of a value to keep it off the cache lines of whatever is allocated after it.
+/// Unlike `CachePadded` this works for values whose alignment can't be chosen, such as the contents of an `Arc` allocated by a lock.
+/// Of two values ending in it, the bytes used by one are always at least `CACHE_LINE` from those used by the other, whichever comes first.
+#[derive(Copy, Clone)]
+pub(crate) struct TrailingPadding {
+    _bytes: [MaybeUninit<u8>; CACHE_LINE],
+}
+impl Default for TrailingPadding {
+    #[inline]
+    fn default() -> Self {
+        Self { _bytes: [MaybeUninit::uninit(); CACHE_LINE] }
+    }
+}
+impl Debug for TrailingPadding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TrailingPadding")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache_padded::{CachePadded, TrailingPadding, CACHE_LINE};
+    use crate::slot::Slot;
+    use std::mem::{align_of, size_of};
+
+    #[test]
+    fn layout_test() {
+        assert_eq!(align_of::<CachePadded<u8>>(), CACHE_LINE);
+        assert_eq!(size_of::<CachePadded<u8>>(), CACHE_LINE);
+        assert_eq!(size_of::<CachePadded<[u8; 129]>>(), CACHE_LINE * (129 / CACHE_LINE + 1));
+        assert_eq!(size_of::<TrailingPadding>(), CACHE_LINE);
+        assert_eq!(align_of::<TrailingPadding>(), 1);
+        assert_eq!(*CachePadded::new(5), 5);
+        // Every slot keeps the next thing allocated a cache line away from its data.
+        assert!(size_of::<Slot<u8>>() >= size_of::<u64>() + CACHE_LINE);
+    }
+}
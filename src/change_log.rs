@@ -0,0 +1,131 @@
+//! Change logs used by `CyclerWriterReplay` to bring recycled blocks up to date.
+//! Instead of cloning the whole previously written block into a recycled one only the changes the recycled block missed are replayed.
+
+use crate::traits::ReplayChanges;
+use std::collections::VecDeque;
+
+/// Bounded history of the changes made to a block, tagged with the version they were published in.
+/// This is stored in the block itself, usually in data only the writer can access, and is kept up to date by the cycler.
+#[derive(Clone, Debug)]
+pub struct ChangeLog<C> {
+    /// Changes in the order they were made along with the version they were published in.
+    changes: VecDeque<(u64, C)>,
+    /// Every change made in a version after this is still in `changes`.
+    complete_after: u64,
+    capacity: usize,
+}
+impl<C> ChangeLog<C> {
+    /// Creates a change log that remembers at most `capacity` changes.
+    /// Blocks that have missed more changes than this are cloned instead.
+    pub fn new(capacity: usize) -> Self {
+        Self { changes: VecDeque::with_capacity(capacity), complete_after: 0, capacity }
+    }
+
+    /// The maximum amount of changes remembered.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The amount of changes currently remembered.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns true if no changes are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns true if every change made after `version` is remembered.
+    pub fn covers(&self, version: u64) -> bool {
+        version >= self.complete_after
+    }
+
+    /// Iterates over the changes made after `version` in the order they were made.
+    pub fn changes_after(&self, version: u64) -> impl Iterator<Item = &C> {
+        self.changes.iter().filter(move |(change_version, _)| *change_version > version).map(|(_, change)| change)
+    }
+
+    /// Records `change` as made in `version`, forgetting the oldest change if full.
+    pub(crate) fn record(&mut self, version: u64, change: C) {
+        self.changes.push_back((version, change));
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.changes.len() > self.capacity {
+            if let Some((version, _)) = self.changes.pop_front() {
+                self.complete_after = version;
+            }
+        }
+    }
+}
+impl<C> ChangeLog<C>
+where
+    C: Clone,
+{
+    /// Appends the changes of `from` made after `version`.
+    /// This log must have been a copy of `from` when `version` was published.
+    fn catch_up(&mut self, from: &Self, version: u64) {
+        self.capacity = from.capacity;
+        self.changes.extend(
+            from.changes.iter().filter(|(change_version, _)| *change_version > version).cloned(),
+        );
+        self.trim();
+        self.complete_after = self.complete_after.max(from.complete_after);
+    }
+}
+impl<C> Default for ChangeLog<C> {
+    /// Creates a change log with a capacity of 0, every recycled block will be cloned.
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Brings `to`, last published as `to_version`, up to date with `from` by replaying the changes it missed.
+/// Falls back to `Clone::clone_from` if the changes are no longer remembered or `to` has never been published.
+pub(crate) fn replay_into<T>(to: &mut T, to_version: u64, from: &T)
+where
+    T: ReplayChanges,
+{
+    let log = from.change_log();
+    // Version 0 blocks are the initial values which are not guaranteed to match each other.
+    if to_version == 0 || !log.covers(to_version) {
+        to.clone_from(from);
+        return;
+    }
+    for change in log.changes_after(to_version) {
+        to.apply_change(change);
+    }
+    to.change_log_mut().catch_up(log, to_version);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::change_log::ChangeLog;
+
+    #[test]
+    fn log_test() {
+        let mut log = ChangeLog::new(3);
+        assert!(log.covers(0));
+        log.record(1, 'a');
+        log.record(1, 'b');
+        log.record(2, 'c');
+        assert!(log.covers(0));
+        assert_eq!(log.changes_after(0).collect::<String>(), "abc");
+        assert_eq!(log.changes_after(1).collect::<String>(), "c");
+        log.record(3, 'd');
+        assert_eq!(log.len(), 3);
+        assert!(!log.covers(0));
+        assert!(log.covers(1));
+        assert_eq!(log.changes_after(1).collect::<String>(), "cd");
+
+        let mut behind = ChangeLog::new(3);
+        behind.record(1, 'a');
+        behind.record(1, 'b');
+        behind.catch_up(&log, 1);
+        assert_eq!(behind.changes_after(0).collect::<String>(), "bcd");
+        assert!(!behind.covers(0));
+        assert!(behind.covers(1));
+    }
+}
@@ -0,0 +1,52 @@
+//! Field level dirty tracking used by `#[derive(CloneFrom)]` to only clone the fields that changed.
+//! The partial copy is `CloneDirty::clone_dirty` rather than `Clone::clone_from`, which must still copy everything.
+
+/// Tracks when each field of a struct was last changed.
+/// Every change is given a new stamp, two copies of the same data only differ in the fields whose stamps differ.
+/// All blocks of a cycler must start with equal values for this to hold, as the `_default` and `_cloned` builders do.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DirtyFields {
+    /// The last stamp given out.
+    counter: u64,
+    /// The stamp of the last change to each field, missing fields have never changed.
+    stamps: Vec<u64>,
+}
+impl DirtyFields {
+    /// Creates a tracker where no field has changed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the field at `index` as changed.
+    pub fn mark(&mut self, index: usize) {
+        if self.stamps.len() <= index {
+            self.stamps.resize(index + 1, 0);
+        }
+        self.counter += 1;
+        self.stamps[index] = self.counter;
+    }
+
+    /// Gets the stamp of the last change to the field at `index`, 0 if it has never changed.
+    pub fn stamp(&self, index: usize) -> u64 {
+        self.stamps.get(index).copied().unwrap_or(0)
+    }
+
+    /// Returns true if the field at `index` differs between the data tracked by `self` and `other`.
+    pub fn differs(&self, other: &Self, index: usize) -> bool {
+        self.stamp(index) != other.stamp(index)
+    }
+}
+
+/// Data that can copy only the fields that changed since it was last a copy of another block.
+/// `#[derive(CloneFrom)]` implements this for structs with a `#[cycler(dirty)]` field.
+pub trait CloneDirty: Clone {
+    /// Clones the fields of `source` that changed since `self` was a copy of it.
+    /// Only correct if `self` was a copy of an earlier version of `source` and every change since was marked,
+    /// as holds for the blocks of a cycler built from equal values and only changed through the write view.
+    fn clone_dirty(&mut self, source: &Self);
+}
+
+/// Clone function copying only the changed fields, passed to `CyclerWriterFn::write_next_fn` in place of `write_next`.
+pub fn clone_dirty<T>(to: &mut T, from: &T) where T: CloneDirty {
+    to.clone_dirty(from);
+}
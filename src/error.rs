@@ -0,0 +1,61 @@
+//! Errors returned by the cyclers.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Returned when a reader can't be added because the cycler already has the most slots it can index.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ReaderLimitError {
+    max_slots: usize,
+}
+impl ReaderLimitError {
+    pub(crate) fn new(max_slots: usize) -> Self {
+        Self { max_slots }
+    }
+
+    /// The most slots the cycler can have.
+    pub fn max_slots(&self) -> usize {
+        self.max_slots
+    }
+}
+impl Display for ReaderLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cycler already has the maximum of {} slots, no reader can be added", self.max_slots)
+    }
+}
+impl Error for ReaderLimitError {}
+
+/// Returned when a cycler can't be built from the given slots or amount of readers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum CyclerBuildError {
+    /// Fewer than 3 slots were given, a cycler needs one for the writer, one per reader and a spare one.
+    TooFewSlots {
+        /// The amount of slots given.
+        slots: usize,
+    },
+    /// More slots were given than the cycler can have.
+    TooManySlots {
+        /// The amount of slots given.
+        slots: usize,
+        /// The most slots the cycler can have.
+        max_slots: usize,
+    },
+    /// More readers were asked for than the cycler can have.
+    TooManyReaders {
+        /// The amount of readers asked for.
+        readers: usize,
+        /// The most readers the cycler can have.
+        max_readers: usize,
+    },
+}
+impl Display for CyclerBuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewSlots { slots } => write!(f, "a cycler needs at least 3 slots, {} were given", slots),
+            Self::TooManySlots { slots, max_slots } => write!(f, "a cycler can have at most {} slots, {} were given", max_slots, slots),
+            Self::TooManyReaders { readers, max_readers } => write!(f, "a cycler can have at most {} readers, {} were asked for", max_readers, readers),
+        }
+    }
+}
+impl Error for CyclerBuildError {}
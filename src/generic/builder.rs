@@ -0,0 +1,105 @@
+use crate::error::CyclerBuildError;
+use crate::generic::{Cycler, GenericCyclerReader, GenericCyclerWriter};
+use crate::notifier::WakerSlot;
+use crate::slot_storage::SlotStorage;
+use crate::slot_table::MAX_SLOTS;
+use std::sync::Arc;
+
+/// The writer and reader of a single reader cycler.
+type SingleReader<T, L> = (GenericCyclerWriter<T, L>, GenericCyclerReader<T, L>);
+/// The writer and readers of a multi reader cycler.
+pub(crate) type MultipleReader<T, L> = (GenericCyclerWriter<T, L>, Vec<GenericCyclerReader<T, L>>);
+
+/// The most slots a cycler with `L` slots can have.
+pub fn max_slots<T, L>() -> usize where L: SlotStorage<T> {
+    MAX_SLOTS.min(L::MAX_READERS.saturating_add(1))
+}
+
+/// Checks a cycler with `L` slots can have `num_readers` readers and `spare_slots` spare slots, returning the amount of slots needed.
+pub(crate) fn slots_for_readers<T, L>(num_readers: usize, spare_slots: usize) -> Result<usize, CyclerBuildError> where L: SlotStorage<T> {
+    let max_slots = max_slots::<T, L>();
+    let used = match num_readers.checked_add(2) {
+        Some(used) if used <= max_slots => used,
+        _ => return Err(CyclerBuildError::TooManyReaders { readers: num_readers, max_readers: max_slots - 2 }),
+    };
+    match used.checked_add(spare_slots) {
+        Some(slots) if slots < 3 => Err(CyclerBuildError::TooFewSlots { slots }),
+        Some(slots) if slots <= max_slots => Ok(slots),
+        slots => Err(CyclerBuildError::TooManySlots { slots: slots.unwrap_or(usize::MAX), max_slots }),
+    }
+}
+
+/// Creates a single reader cycler with `L` slots using `values` as the initial values for the slots.
+///
+/// # Panics
+/// If the lock can't be read by the writer and a reader at once.
+pub fn build_single_reader<T, L>(values: [T; 3]) -> (GenericCyclerWriter<T, L>, GenericCyclerReader<T, L>)
+where
+    L: SlotStorage<T>,
+{
+    try_build_single_reader(values).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Creates a single reader cycler with `L` slots using `values` as the initial values for the slots.
+/// Fails if the lock can't be read by the writer and a reader at once.
+pub fn try_build_single_reader<T, L>(values: [T; 3]) -> Result<SingleReader<T, L>, CyclerBuildError>
+where
+    L: SlotStorage<T>,
+{
+    let (writer, mut readers) = try_build_multiple_reader(Vec::from(values))?;
+    Ok((writer, readers.pop().unwrap()))
+}
+
+/// Creates a multi reader cycler with `L` slots, the amount of readers being `initial_values.len() - 2`.
+///
+/// # Panics
+/// If there are fewer than 3 initial values or more than the cycler can have slots.
+pub fn build_multiple_reader<T, L>(initial_values: Vec<T>) -> (GenericCyclerWriter<T, L>, Vec<GenericCyclerReader<T, L>>)
+where
+    L: SlotStorage<T>,
+{
+    try_build_multiple_reader(initial_values).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Creates a multi reader cycler with `L` slots, the amount of readers being `initial_values.len() - 2`.
+/// Fails if there are fewer than 3 initial values or more than the cycler can have slots.
+pub fn try_build_multiple_reader<T, L>(initial_values: Vec<T>) -> Result<MultipleReader<T, L>, CyclerBuildError>
+where
+    L: SlotStorage<T>,
+{
+    let num_readers = initial_values.len().saturating_sub(2);
+    build_with_readers(initial_values, num_readers)
+}
+
+/// Creates a cycler with `L` slots and `num_readers` readers, the slots past `num_readers + 2` are spare.
+/// Fails if there are fewer than 3 initial values or more than the cycler can have slots.
+pub(crate) fn build_with_readers<T, L>(initial_values: Vec<T>, num_readers: usize) -> Result<MultipleReader<T, L>, CyclerBuildError>
+where
+    L: SlotStorage<T>,
+{
+    let max_slots = max_slots::<T, L>();
+    let slots = initial_values.len();
+    if slots < 3 {
+        return Err(CyclerBuildError::TooFewSlots { slots });
+    }
+    if slots > max_slots {
+        return Err(CyclerBuildError::TooManySlots { slots, max_slots });
+    }
+    let cycler = Arc::new(Cycler::new(initial_values, num_readers, max_slots));
+    let mut readers = Vec::with_capacity(cycler.num_readers());
+    for _ in 0..cycler.num_readers() {
+        readers.push(GenericCyclerReader {
+            reader: Some(L::try_read(&cycler.data_slots[0]).unwrap()),
+            cycler: cycler.clone(),
+            waker: WakerSlot::default(),
+        })
+    }
+    Ok((
+        GenericCyclerWriter {
+            writer: L::try_write(&cycler.data_slots[1]).unwrap(),
+            cycler,
+            currently_writing: 1,
+        },
+        readers,
+    ))
+}
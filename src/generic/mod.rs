@@ -0,0 +1,218 @@
+//! The cycler every backend is built on, generic over the `SlotStorage` of its slots.
+//! `RwLockCycler` is this cycler with `parking_lot::RwLock` slots, other locks such as `std::sync::RwLock` can be plugged in through `SlotLock`.
+//! `AtomicCycler` is this cycler with `AtomicRwLock` slots stored `Contiguous`ly in the slot table, borrowed by guards instead of each held through an `Arc`.
+//!
+//! ## Layout
+//! Every slot ends in a cache line of padding and `most_up_to_date` is aligned to its own, so a reader locking one slot never contends on a cache line with the writer locking another or publishing.
+//!
+//! ## Memory ordering
+//! A reader must see every write the writer made to a block before publishing it.
+//! The slot locks give this on their own: the writer's writes happen before it downgrades its write lock, a release, and a reader's read lock is an acquire that reads the downgrade or a later unlock of that lock.
+//!
+//! `most_up_to_date` only says which slot to lock, it is stored with `Release` after the downgrade and loaded with `Acquire`.
+//! A reader loading the index of a publish therefore happens after that publish's downgrade.
+//! By coherence its read lock sees the downgrade or later, never the write lock from before it, and it sees the version written by that publish or a newer one, so `read_latest` never takes an older block than the one it loaded the index of.
+//! The `PublishNotifier` version is stored after the index with `SeqCst`, a release, so a reader woken by a publish loads its index or a newer one.
+//! Nothing needs `SeqCst`, there is a single writer so the index has no total order to agree on.
+
+mod builder;
+mod reader;
+mod writer;
+
+pub use builder::{build_multiple_reader, build_single_reader, try_build_multiple_reader, try_build_single_reader};
+pub use builder::max_slots;
+pub(crate) use builder::{build_with_readers, slots_for_readers};
+pub use reader::GenericCyclerReader;
+pub use writer::GenericCyclerWriter;
+
+use crate::cache_padded::CachePadded;
+use crate::notifier::PublishNotifier;
+use crate::slot_storage::SlotStorage;
+use crate::slot_table::SlotTable;
+use crate::sync::AtomicUsize;
+use std::marker::PhantomData;
+use std::sync::atomic::Ordering;
+
+/// The state shared by the writer and readers of a cycler.
+#[derive(Debug)]
+pub(crate) struct Cycler<T, L> where L: SlotStorage<T> {
+    pub(crate) data_slots: SlotTable<L::Handle, L::WriteGuard>,
+    /// Written by every publish and read by every switch, padded so neither slows down accesses to the slot table next to it.
+    pub(crate) most_up_to_date: CachePadded<AtomicUsize>,
+    pub(crate) notifier: PublishNotifier,
+    data: PhantomData<fn() -> T>,
+}
+impl<T, L> Cycler<T, L> where L: SlotStorage<T> {
+    fn new(values: Vec<T>, num_readers: usize, max_slots: usize) -> Self {
+        Self {
+            data_slots: SlotTable::new(L::new_slots(values), num_readers, max_slots),
+            most_up_to_date: CachePadded::new(AtomicUsize::new(0)),
+            notifier: PublishNotifier::default(),
+            data: PhantomData,
+        }
+    }
+}
+impl<T, L> Cycler<T, L> where L: SlotStorage<T> {
+    pub(crate) fn num_readers(&self) -> usize {
+        self.data_slots.num_readers()
+    }
+
+    /// Write locks the slot at `index` to retire it, unless it is taken or the latest.
+    fn try_retire(&self, slot: &L::Handle, index: usize) -> Option<L::WriteGuard> {
+        let guard = L::try_write(slot)?;
+        // The writer only publishes the slot it holds, so the latest slot can't change to this one while it is locked here.
+        (self.most_up_to_date.load(Ordering::Acquire) != index).then_some(guard)
+    }
+
+    /// Unregisters a reader that has released its slot, retiring the slots no longer needed.
+    pub(crate) fn remove_reader(&self) {
+        self.data_slots.remove_reader(|slot, index| self.try_retire(slot, index));
+    }
+
+    /// Retires the slots left by dropped readers that were taken when they were dropped, called by the writer after publishing.
+    #[inline]
+    pub(crate) fn shrink(&self) {
+        if self.data_slots.needs_shrink() {
+            self.data_slots.shrink(|slot, index| self.try_retire(slot, index));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "allow_unsafe"))]
+mod test {
+    use crate::generic::{build_multiple_reader, build_single_reader, GenericCyclerReader, GenericCyclerWriter};
+    use crate::slot::Slot;
+    use crate::testing::{replay_test, single_reader_test, TestData};
+    use crate::{CyclerReader, CyclerReaderAsync, CyclerWriterDefault, ReadAccess, WriteAccess};
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::RwLock;
+    use std::task::{Context, Waker};
+
+    type StdWriter<T> = GenericCyclerWriter<T, RwLock<Slot<T>>>;
+    type StdReader<T> = GenericCyclerReader<T, RwLock<Slot<T>>>;
+
+    #[test]
+    fn std_lock_test() {
+        let (mut writer, mut reader): (StdWriter<TestData>, StdReader<TestData>) =
+            build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+        let new_data = TestData { test1: 100, test2: "Test2".to_string(), test3: Box::new(1002) };
+        writer.write_data_mut().clone_from(&new_data);
+        assert_eq!(writer.write_next(), 1);
+        assert_eq!(*writer.write_data(), new_data);
+        assert_eq!(*reader.read_data(), TestData::default());
+        assert!(reader.read_latest());
+        assert_eq!(*reader.read_data(), new_data);
+
+        let mut added = writer.add_reader().unwrap();
+        assert_eq!(added.version(), 1);
+        let cloned = added.try_clone().unwrap();
+        assert_eq!(writer.cycler.data_slots.len(), 5);
+        drop(cloned);
+
+        // The guards of `std::sync::RwLock` can't be sent so everything stays on this thread.
+        let (mut other, mut readers): (StdWriter<TestData>, Vec<StdReader<TestData>>) = build_multiple_reader(vec![TestData::default(); 4]);
+        for version in 2..100 {
+            writer.write_data_mut().test1 = version;
+            other.write_data_mut().test1 = version;
+            writer.write_next();
+            other.write_next();
+            assert!(added.read_latest());
+            assert_eq!(added.read_data().test1, version);
+            for reader in readers.iter_mut().take(version % 3) {
+                reader.read_latest();
+                assert_eq!(reader.read_data().test1 as u64, reader.version() + 1);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "atomic_rw_lock")]
+    fn reader_limit_test() {
+        use crate::atomic_rw_lock::AtomicRwLock;
+        use crate::error::CyclerBuildError;
+        use crate::generic::try_build_multiple_reader;
+        use crate::sync::AtomicU8;
+
+        type U8Writer = GenericCyclerWriter<usize, AtomicRwLock<Slot<usize>, AtomicU8>>;
+
+        // A `u8` counter allows 254 readers of a slot, the writer and 253 readers.
+        let (writer, mut readers): (U8Writer, Vec<_>) = build_multiple_reader(vec![0; 255]);
+        assert_eq!(readers.len(), 253);
+        let too_many: Result<(U8Writer, Vec<_>), _> = try_build_multiple_reader(vec![0; 256]);
+        assert_eq!(too_many.unwrap_err(), CyclerBuildError::TooManySlots { slots: 256, max_slots: 255 });
+        let error = writer.add_reader().unwrap_err();
+        assert_eq!(error.max_slots(), 255);
+        assert_eq!(error.to_string(), "cycler already has the maximum of 255 slots, no reader can be added");
+        assert_eq!(readers[0].try_clone().unwrap_err(), error);
+        readers.pop();
+        readers.push(writer.add_reader().unwrap());
+    }
+
+    #[test]
+    fn shrink_test() {
+        let (mut writer, mut reader): (StdWriter<TestData>, StdReader<TestData>) =
+            build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+        let mut added: Vec<_> = (0..10).map(|_| writer.add_reader().unwrap()).collect();
+        assert_eq!(writer.cycler.data_slots.len(), 13);
+        for version in 1..20 {
+            writer.write_data_mut().test1 = version;
+            writer.write_next();
+            added.iter_mut().take(version % 10).for_each(|reader| assert!(reader.read_latest()));
+        }
+        // The slots added for the dropped readers are retired, those taken or the latest once the writer moves on.
+        drop(added);
+        assert!(writer.cycler.data_slots.len() < 13);
+        for version in 20..30 {
+            writer.write_data_mut().test1 = version;
+            writer.write_next();
+            assert!(reader.read_latest());
+            assert_eq!(reader.read_data().test1, version);
+        }
+        assert_eq!(writer.cycler.data_slots.len(), 3);
+
+        // Retired slots are revived with a clone of the block being written.
+        let mut added = writer.add_reader().unwrap();
+        let _other = added.try_clone().unwrap();
+        assert_eq!(writer.cycler.data_slots.len(), 5);
+        for version in 30..40 {
+            writer.write_data_mut().test1 = version;
+            writer.write_next();
+            assert!(added.read_latest());
+            assert_eq!(added.read_data().test1, version);
+        }
+    }
+
+    #[test]
+    fn dropped_future_test() {
+        let (mut writer, mut readers): (StdWriter<TestData>, Vec<StdReader<TestData>>) = build_multiple_reader(vec![TestData::default(); 5]);
+        let mut cx = Context::from_waker(Waker::noop());
+        for _ in 0..10 {
+            for reader in &mut readers {
+                assert!(pin!(reader.read_latest_async()).poll(&mut cx).is_pending());
+            }
+            // Each future unregisters its task when dropped.
+            assert_eq!(writer.cycler.notifier.waiting(), 0);
+        }
+
+        let mut futures: Vec<_> = readers.iter_mut().map(|reader| Box::pin(reader.read_latest_async())).collect();
+        for _ in 0..10 {
+            for future in &mut futures {
+                assert!(future.as_mut().poll(&mut cx).is_pending());
+            }
+        }
+        // Polling again replaces the task of each reader.
+        assert_eq!(writer.cycler.notifier.waiting(), 3);
+        drop(futures.pop());
+        assert_eq!(writer.cycler.notifier.waiting(), 2);
+        writer.write_next();
+        assert_eq!(writer.cycler.notifier.waiting(), 0);
+        assert!(futures.iter_mut().all(|future| future.as_mut().poll(&mut cx).is_ready()));
+    }
+
+    #[test]
+    fn std_lock_suite_test() {
+        single_reader_test(build_multiple_reader::<_, RwLock<Slot<_>>>);
+        replay_test(build_multiple_reader::<_, RwLock<Slot<_>>>);
+    }
+}
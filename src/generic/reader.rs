@@ -0,0 +1,114 @@
+use crate::error::ReaderLimitError;
+use crate::generic::Cycler;
+use crate::notifier::WakerSlot;
+use crate::slot::Slot;
+use crate::slot_storage::SlotStorage;
+use crate::traits::{CyclerReader, CyclerReaderAsync, CyclerReaderWait, ReadAccess};
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// The reader for a cycler with `L` slots
+pub struct GenericCyclerReader<T, L> where L: SlotStorage<T> {
+    pub(crate) cycler: Arc<Cycler<T, L>>,
+    pub(crate) reader: Option<L::ReadGuard>,
+    pub(crate) waker: WakerSlot,
+}
+impl<T, L> GenericCyclerReader<T, L> where L: SlotStorage<T> {
+    /// Registers a new reader of the latest published block, adding a slot made from `new_value` if there is no spare one.
+    pub(crate) fn register(cycler: Arc<Cycler<T, L>>, new_value: impl FnOnce() -> T) -> Result<Self, ReaderLimitError> {
+        cycler.data_slots.add_reader(new_value, L::new_slot)?;
+        loop {
+            let most_up_to_date = cycler.most_up_to_date.load(Ordering::Acquire);
+            if let Some(reader) = L::try_read(&cycler.data_slots[most_up_to_date]) {
+                return Ok(Self { cycler, reader: Some(reader), waker: WakerSlot::default() });
+            }
+            crate::sync::spin_loop();
+        }
+    }
+
+    #[inline]
+    fn slot(&self) -> &Slot<T> {
+        // Reader should never be None except inside of read_latest
+        self.reader.as_ref().unwrap()
+    }
+}
+impl<T, L> GenericCyclerReader<T, L> where T: Clone, L: SlotStorage<T> {
+    /// Creates another reader of this cycler reading the latest published block.
+    /// A spare slot is used if there is one, otherwise a clone of the block this reader holds refills a slot retired by dropped readers or is added in a new slot.
+    /// Fails if the cycler already has the most slots it can index.
+    pub fn try_clone(&self) -> Result<Self, ReaderLimitError> {
+        Self::register(self.cycler.clone(), || self.slot().data().clone())
+    }
+}
+/// Dropping a reader retires the slots added past the ones the cycler was built with that are no longer needed, dropping their blocks.
+impl<T, L> Drop for GenericCyclerReader<T, L> where L: SlotStorage<T> {
+    fn drop(&mut self) {
+        // The slot is released first so it is free once it becomes spare.
+        drop(self.reader.take());
+        self.cycler.notifier.unregister(&mut self.waker);
+        self.cycler.remove_reader();
+    }
+}
+impl<T, L> Debug for GenericCyclerReader<T, L> where T: Debug, L: SlotStorage<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GenericCyclerReader").field("slot", self.slot()).finish()
+    }
+}
+impl<T, L> ReadAccess for GenericCyclerReader<T, L> where T: ReadAccess, L: SlotStorage<T> {
+    type Read = T::Read;
+
+    #[inline]
+    fn read_data(&self) -> &Self::Read {
+        self.slot().data().read_data()
+    }
+}
+impl<T, L> CyclerReader<T> for GenericCyclerReader<T, L> where T: ReadAccess, L: SlotStorage<T> {
+    fn read_latest(&mut self) -> bool {
+        let previous = self.version();
+        drop(self.reader.take());
+        let mut most_up_to_date = self.cycler.most_up_to_date.load(Ordering::Acquire);
+        loop {
+            if let Some(reader) = L::try_read(&self.cycler.data_slots[most_up_to_date]) {
+                // A slot older than the one just released is rejected so the version never goes backwards.
+                if reader.version >= previous {
+                    self.reader = Some(reader);
+                    return self.version() != previous;
+                }
+            }
+            crate::sync::spin_loop();
+            most_up_to_date = self.cycler.most_up_to_date.load(Ordering::Acquire);
+        }
+    }
+
+    #[inline]
+    fn version(&self) -> u64 {
+        self.slot().version
+    }
+}
+impl<T, L> CyclerReaderAsync<T> for GenericCyclerReader<T, L> where T: ReadAccess, L: SlotStorage<T> {
+    fn poll_read_latest(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let seen = self.version();
+        if self.cycler.notifier.poll_newer_than(&mut self.waker, seen, cx) {
+            self.read_latest();
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn cancel_read_latest(&mut self) {
+        self.cycler.notifier.unregister(&mut self.waker);
+    }
+}
+impl<T, L> CyclerReaderWait<T> for GenericCyclerReader<T, L> where T: ReadAccess, L: SlotStorage<T> {
+    fn wait_for_update(&self, timeout: Duration) -> bool {
+        self.cycler.notifier.wait_newer_than(self.version(), Instant::now().checked_add(timeout))
+    }
+
+    fn wait_for_update_until(&self, deadline: Instant) -> bool {
+        self.cycler.notifier.wait_newer_than(self.version(), Some(deadline))
+    }
+}
@@ -0,0 +1,187 @@
+use crate::change_log::replay_into;
+use crate::error::ReaderLimitError;
+use crate::generic::{Cycler, GenericCyclerReader};
+use crate::slot::{CloneContext, Slot};
+use crate::slot_storage::SlotStorage;
+use crate::traits::*;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// The writer to a cycler with `L` slots
+pub struct GenericCyclerWriter<T, L> where L: SlotStorage<T> {
+    // Declared before `cycler` so it is dropped while the slot it may borrow is still alive.
+    pub(crate) writer: L::WriteGuard,
+    pub(crate) cycler: Arc<Cycler<T, L>>,
+    pub(crate) currently_writing: usize,
+}
+impl<T, L> GenericCyclerWriter<T, L> where L: SlotStorage<T> {
+    /// Locks the next free slot and publishes the current one, returning the version published.
+    /// `clone_fn` is given the newly locked slot, the slot being published and the index of the newly locked slot.
+    fn publish(&mut self, clone_fn: impl FnOnce(&mut Slot<T>, &Slot<T>, usize)) -> u64 {
+        let mut next_write = (self.currently_writing + 1) % self.cycler.data_slots.len();
+        loop {
+            if let Some(mut writer) = L::try_write(&self.cycler.data_slots[next_write]) {
+                std::mem::swap(&mut self.writer, &mut writer);
+                let version = self.cycler.notifier.latest_version() + 1;
+                writer.version = version;
+                let old = L::downgrade(writer);
+                clone_fn(&mut self.writer, &old, next_write);
+                self.cycler.most_up_to_date.store(self.currently_writing, Ordering::Release);
+                self.cycler.notifier.notify(version);
+                self.currently_writing = next_write;
+                self.cycler.shrink();
+                return version;
+            } else {
+                next_write = (next_write + 1) % self.cycler.data_slots.len();
+                // Every other slot looked taken, they can only be briefly as readers hold at most one each.
+                if next_write == self.currently_writing {
+                    crate::sync::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// Like `publish` but `clone_fn` gets exclusive access to the slot being published, which is published after it returns.
+    fn publish_mut(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64 {
+        let mut next_write = (self.currently_writing + 1) % self.cycler.data_slots.len();
+        loop {
+            if let Some(mut writer) = L::try_write(&self.cycler.data_slots[next_write]) {
+                std::mem::swap(&mut self.writer, &mut writer);
+                let version = self.cycler.notifier.latest_version() + 1;
+                clone_fn(self.writer.data_mut(), writer.data_mut());
+                writer.version = version;
+                // Held until the index is stored so the slot can't be retired before it is the latest.
+                let old = L::downgrade(writer);
+                self.cycler.most_up_to_date.store(self.currently_writing, Ordering::Release);
+                drop(old);
+                self.cycler.notifier.notify(version);
+                self.currently_writing = next_write;
+                self.cycler.shrink();
+                return version;
+            } else {
+                next_write = (next_write + 1) % self.cycler.data_slots.len();
+                // Every other slot looked taken, they can only be briefly as readers hold at most one each.
+                if next_write == self.currently_writing {
+                    crate::sync::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// Publishes calling `clone_fn` with a `CloneContext` for the newly locked slot.
+    fn publish_context(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64 {
+        self.publish(|to, from, slot| {
+            let context = CloneContext { target_version: to.version, source_version: from.version, slot };
+            clone_fn(to.data_mut(), from.data(), context)
+        })
+    }
+}
+impl<T, L> GenericCyclerWriter<T, L> where T: Clone, L: SlotStorage<T> {
+    /// Adds a reader reading the latest published block.
+    /// A spare slot is used if there is one, otherwise a clone of the block being written refills a slot retired by dropped readers or is added in a new slot.
+    /// Fails if the cycler already has the most slots it can index.
+    pub fn add_reader(&self) -> Result<GenericCyclerReader<T, L>, ReaderLimitError> {
+        GenericCyclerReader::register(self.cycler.clone(), || self.writer.data().clone())
+    }
+}
+impl<T, L> Debug for GenericCyclerWriter<T, L> where T: Debug, L: SlotStorage<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GenericCyclerWriter")
+            .field("currently_writing", &self.currently_writing)
+            .field("slot", &*self.writer)
+            .finish()
+    }
+}
+impl<T, L> ReadAccess for GenericCyclerWriter<T, L> where T: ReadAccess, L: SlotStorage<T> {
+    type Read = T::Read;
+
+    /// Gets a shared reference to the read data of the current block
+    #[inline]
+    fn read_data(&self) -> &Self::Read {
+        self.writer.data().read_data()
+    }
+}
+impl<T, L> WriteAccess for GenericCyclerWriter<T, L> where T: WriteAccess, L: SlotStorage<T> {
+    type Write = T::Write;
+
+    /// Gets a shared reference to the write data of the current block
+    #[inline]
+    fn write_data(&self) -> &Self::Write {
+        self.writer.data().write_data()
+    }
+
+    /// Gets an exclusive reference to the write data of the current block
+    #[inline]
+    fn write_data_mut(&mut self) -> &mut Self::Write {
+        self.writer.data_mut().write_data_mut()
+    }
+}
+impl<T, L> CyclerWriter<T> for GenericCyclerWriter<T, L> where T: WriteAccess, L: SlotStorage<T> {}
+impl<T, L> CyclerWriterFn<T> for GenericCyclerWriter<T, L> where T: WriteAccess, L: SlotStorage<T> {
+    fn write_next_fn(&mut self, clone_fn: fn(&mut T, &T)) -> u64 {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+
+    fn write_next_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T)) -> u64 where Self: Sized {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+
+    fn write_next_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T)) -> u64 {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+
+    fn write_next_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T)>) -> u64 {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+}
+impl<T, L> CyclerWriterMutFn<T> for GenericCyclerWriter<T, L> where T: WriteAccess, L: SlotStorage<T> {
+    fn write_next_mut_fn(&mut self, clone_fn: fn(&mut T, &mut T)) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64 where Self: Sized {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &mut T)) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &mut T)>) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+}
+impl<T, L> CyclerWriterDefault<T> for GenericCyclerWriter<T, L> where T: Clone + WriteAccess, L: SlotStorage<T> {
+    fn write_next(&mut self) -> u64 {
+        self.write_next_fn(T::clone_from)
+    }
+}
+impl<T, L> CyclerWriterContextFn<T> for GenericCyclerWriter<T, L> where T: WriteAccess, L: SlotStorage<T> {
+    fn write_next_context_fn(&mut self, clone_fn: fn(&mut T, &T, CloneContext)) -> u64 {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64 where Self: Sized {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T, CloneContext)) -> u64 {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T, CloneContext)>) -> u64 {
+        self.publish_context(clone_fn)
+    }
+}
+impl<T, L> CyclerWriterReplay<T> for GenericCyclerWriter<T, L> where T: WriteAccess + ReplayChanges, L: SlotStorage<T> {
+    fn write_change(&mut self, change: T::Change) {
+        let version = self.cycler.notifier.latest_version() + 1;
+        self.writer.data_mut().apply_change(&change);
+        self.writer.data_mut().change_log_mut().record(version, change);
+    }
+
+    fn write_next_replay(&mut self) -> u64 {
+        self.write_next_context_fn(|to, from, context| replay_into(to, context.target_version, from))
+    }
+}
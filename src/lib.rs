@@ -5,7 +5,9 @@
 //! ## Requirements
 //! The data type must also implement both `ReadAccess` and `WriteAccess` otherwise the access functions won't be available.
 //! These types allow the reader to see a subset of the data, intended for hiding data only the writer needs like a change log for optimization of the copy.
+//! Implementing `ReplayChanges` with a `ChangeLog` allows `CyclerWriterReplay` to bring recycled blocks up to date by replaying only the changes they missed.
 //! If you don't care about access restrictions you can set `ReadAccess::Read` and `WriteAccess::Write` to Self and there will be zero runtime cost as the compiler should optimize that out.
+//! With the `derive` feature `#[derive(CyclerData)]` implements both, optionally generating a read view that hides fields marked `#[cycler(write_only)]`.
 //!
 //! ## Optimization
 //! The most optimal thing to do with regard to memory usage is to have a single reader.
@@ -14,40 +16,115 @@
 //! What this means is that if you only utilize a single writer and distribute that the minimum amount of memory will be used.
 //! The trade off is that if you have multiple reading loops that operate at differing rates all the loops will operate at the slowest speed.
 //! Adding readers does not increase the amount of data to copy and may only slightly increase the time between copy switches.
+//! For small `Copy` data the `SeqLockCycler` of the `seq_lock` feature keeps only one shared copy, its readers copying it out, so adding readers adds no slots.
+//! With the `triple_buffer` feature a single reader DefaultCycler is a `TripleBufferCycler`, which switches with one atomic swap instead of locking slots.
 //!
 //! This also is based on the `clone_from` idea to clone values which is not implemented by derive normally (Derivative can auto derive for you).
+//! With the `derive` feature `#[derive(CloneFrom)]` generates a field by field `clone_from`, and a field marked `#[cycler(dirty)]` holding `DirtyFields` adds `CloneDirty`, copying only the fields changed through the write view when passed to `write_next_fn`.
 //! This is a major optimization chance in this case and you can test/track the changes to reduce copy time.
 #![warn(missing_debug_implementations, rust_2018_idioms, missing_docs, unused_import_braces)]
 #![cfg_attr(not(feature = "allow_unsafe"), forbid(unsafe_code))]
 
 
 
-#[macro_use]
-mod macros;
-
+mod async_read;
+mod builder;
+mod cache_padded;
+mod change_log;
+mod dirty;
+mod error;
+pub mod generic;
+mod notifier;
 pub mod rw_lock_cycler;
+mod slot;
+mod slot_lock;
+mod slot_storage;
+mod slot_table;
+mod sync;
 mod traits;
 
 #[cfg(feature = "atomic_cycler")]
 pub mod atomic_cycler;
-#[cfg(feature = "atomic_cycler")]
-mod atomic_rw_lock;
-#[cfg(feature = "unsafe_cleanup")]
-mod static_ref_holder;
-#[cfg(test)]
-mod test;
+#[cfg(feature = "atomic_rw_lock")]
+pub mod atomic_rw_lock;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "seq_lock")]
+pub mod seq_lock_cycler;
+#[cfg(feature = "triple_buffer")]
+pub mod triple_buffer_cycler;
 
 /// This is the currently most optimal cycler writer that implements `UniversalCyclerWriter`.
-pub type DefaultCyclerWriter<T> = RwLockCyclerWriter<T>;
+/// `R` is `SingleReader` for the writer of a single reader DefaultCycler, which can use a cycler that can't have more readers.
+pub type DefaultCyclerWriter<T, R = MultipleReaders> = <R as DefaultReaders>::Writer<T>;
 /// This is the currently most optimal cycler reader that implements `UniversalCyclerReader`.
-pub type DefaultCyclerReader<T> = RwLockCyclerReader<T>;
+/// `R` is `SingleReader` for the reader of a single reader DefaultCycler, which can use a cycler that can't have more readers.
+pub type DefaultCyclerReader<T, R = MultipleReaders> = <R as DefaultReaders>::Reader<T>;
+/// This is the currently most optimal cycler backend for `CyclerBuilder`, the backend of the multiple reader DefaultCycler.
+pub type DefaultBackend = RwLockBackend;
+/// The writer and reader of a single reader DefaultCycler.
+type DefaultSingleReader<T> = (DefaultCyclerWriter<T, SingleReader>, DefaultCyclerReader<T, SingleReader>);
+/// The lock around each slot of the DefaultCycler.
+type DefaultSlotStorage<T> = <DefaultBackend as CyclerBackend>::Storage<T>;
 
+pub use async_read::ReadLatestFuture;
+pub use builder::{BackendReader, BackendWriter, BuiltCycler, CyclerBackend, CyclerBuilder};
+pub use rw_lock_cycler::RwLockBackend;
+#[cfg(feature = "atomic_cycler")]
+pub use atomic_cycler::AtomicBackend;
+pub use change_log::ChangeLog;
+pub use dirty::{clone_dirty, CloneDirty, DirtyFields};
+pub use error::{CyclerBuildError, ReaderLimitError};
+pub use slot::{CloneContext, Slot};
+pub use slot_lock::SlotLock;
+pub use slot_storage::SlotStorage;
+#[cfg(feature = "atomic_rw_lock")]
+pub use slot_storage::Contiguous;
+#[cfg(feature = "allow_unsafe")]
+pub use slot_lock::{StdRwLockReadGuard, StdRwLockWriteGuard};
+#[cfg(feature = "stream")]
+pub use async_read::ReaderStream;
 pub use traits::*;
+#[cfg(feature = "derive")]
+pub use cycler_derive::{CloneFrom, CyclerData};
 
 use crate::rw_lock_cycler::{RwLockCyclerReader, RwLockCyclerWriter};
+#[cfg(feature = "triple_buffer")]
+use crate::triple_buffer_cycler::{TripleBufferCyclerReader, TripleBufferCyclerWriter};
+
+/// How many readers a DefaultCycler can have, choosing the writer and reader it uses.
+pub trait DefaultReaders {
+    /// The writer of the DefaultCycler.
+    type Writer<T>;
+    /// The reader of the DefaultCycler.
+    type Reader<T>;
+}
+
+/// A DefaultCycler built with a single reader, the `TripleBufferCycler` with the `triple_buffer` feature.
+/// Its readers can't be added or cloned, `rw_lock_cycler::build_single_reader` builds a single reader cycler that can.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SingleReader;
+#[cfg(feature = "triple_buffer")]
+impl DefaultReaders for SingleReader {
+    type Writer<T> = TripleBufferCyclerWriter<T>;
+    type Reader<T> = TripleBufferCyclerReader<T>;
+}
+#[cfg(not(feature = "triple_buffer"))]
+impl DefaultReaders for SingleReader {
+    type Writer<T> = RwLockCyclerWriter<T>;
+    type Reader<T> = RwLockCyclerReader<T>;
+}
+
+/// A DefaultCycler built with any number of readers.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MultipleReaders;
+impl DefaultReaders for MultipleReaders {
+    type Writer<T> = RwLockCyclerWriter<T>;
+    type Reader<T> = RwLockCyclerReader<T>;
+}
 
 /// Creates a single reader DefaultCycler using default initial values for the slots.
-pub fn build_single_reader_default<T>() -> (DefaultCyclerWriter<T>, DefaultCyclerReader<T>)
+pub fn build_single_reader_default<T>() -> DefaultSingleReader<T>
 where
     T: Default,
 {
@@ -57,7 +134,7 @@ where
 /// Creates a single reader DefaultCycler cloning `initial_value` as the initial values for the slots.
 pub fn build_single_reader_cloned<T>(
     initial_value: T,
-) -> (DefaultCyclerWriter<T>, DefaultCyclerReader<T>)
+) -> DefaultSingleReader<T>
 where
     T: Clone,
 {
@@ -67,34 +144,89 @@ where
 /// Creates a single reader DefaultCycler using `initial_values` as the initial values for the slots.
 pub fn build_single_reader<T>(
     initial_values: [T; 3],
-) -> (DefaultCyclerWriter<T>, DefaultCyclerReader<T>) {
+) -> DefaultSingleReader<T> {
+    #[cfg(feature = "triple_buffer")]
+    return triple_buffer_cycler::build_single_reader(initial_values);
+    #[cfg(not(feature = "triple_buffer"))]
     rw_lock_cycler::build_single_reader(initial_values)
 }
 
+/// Creates a single reader DefaultCycler using `initial_values` as the initial values for the slots.
+pub fn try_build_single_reader<T>(
+    initial_values: [T; 3],
+) -> Result<DefaultSingleReader<T>, CyclerBuildError> {
+    #[cfg(feature = "triple_buffer")]
+    return Ok(triple_buffer_cycler::build_single_reader(initial_values));
+    #[cfg(not(feature = "triple_buffer"))]
+    rw_lock_cycler::try_build_single_reader(initial_values)
+}
+
 /// Creates a multi reader DefaultCycler with the initial value being default.
+///
+/// # Panics
+/// If `num_readers` is 0 or more than the cycler can have.
 pub fn build_multiple_reader_default<T>(
-    num_readers: u8,
+    num_readers: usize,
 ) -> (DefaultCyclerWriter<T>, Vec<DefaultCyclerReader<T>>)
 where
     T: Default,
 {
-    build_multiple_reader((0..num_readers + 2).map(|_| T::default()).collect())
+    try_build_multiple_reader_default(num_readers).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Creates a multi reader DefaultCycler with the initial value being default.
+/// Fails if `num_readers` is 0 or more than the cycler can have.
+pub fn try_build_multiple_reader_default<T>(
+    num_readers: usize,
+) -> Result<(DefaultCyclerWriter<T>, Vec<DefaultCyclerReader<T>>), CyclerBuildError>
+where
+    T: Default,
+{
+    let slots = generic::slots_for_readers::<T, DefaultSlotStorage<T>>(num_readers, 0)?;
+    try_build_multiple_reader((0..slots).map(|_| T::default()).collect())
 }
 
 /// Creates a multi reader DefaultCycler with the initial value being cloned.
+///
+/// # Panics
+/// If `num_readers` is 0 or more than the cycler can have.
 pub fn build_multiple_reader_cloned<T>(
     initial_value: T,
-    num_readers: u8,
+    num_readers: usize,
 ) -> (DefaultCyclerWriter<T>, Vec<DefaultCyclerReader<T>>)
 where
     T: Clone,
 {
-    build_multiple_reader(vec![initial_value; num_readers as usize + 2])
+    try_build_multiple_reader_cloned(initial_value, num_readers).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Creates a multi reader DefaultCycler with the initial value being cloned.
+/// Fails if `num_readers` is 0 or more than the cycler can have.
+pub fn try_build_multiple_reader_cloned<T>(
+    initial_value: T,
+    num_readers: usize,
+) -> Result<(DefaultCyclerWriter<T>, Vec<DefaultCyclerReader<T>>), CyclerBuildError>
+where
+    T: Clone,
+{
+    let slots = generic::slots_for_readers::<T, DefaultSlotStorage<T>>(num_readers, 0)?;
+    try_build_multiple_reader(vec![initial_value; slots])
 }
 
 /// Creates a multi reader DefaultCycler, the amount of readers being `initial_values.len() - 2`.
+///
+/// # Panics
+/// If there are fewer than 3 initial values or more than the cycler can have slots.
 pub fn build_multiple_reader<T>(
     initial_values: Vec<T>,
 ) -> (DefaultCyclerWriter<T>, Vec<DefaultCyclerReader<T>>) {
     rw_lock_cycler::build_multiple_reader(initial_values)
 }
+
+/// Creates a multi reader DefaultCycler, the amount of readers being `initial_values.len() - 2`.
+/// Fails if there are fewer than 3 initial values or more than the cycler can have slots.
+pub fn try_build_multiple_reader<T>(
+    initial_values: Vec<T>,
+) -> Result<(DefaultCyclerWriter<T>, Vec<DefaultCyclerReader<T>>), CyclerBuildError> {
+    rw_lock_cycler::try_build_multiple_reader(initial_values)
+}
@@ -0,0 +1,198 @@
+//! Publish notification shared by the cycler backends.
+//! The writer calls `notify` after every publish so readers can wait for a new block, either as a task or a parked thread, instead of spinning on `read_latest`.
+
+use parking_lot::{Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::task::{Context, Waker};
+use std::time::Instant;
+
+/// The waker slot of a reader in its cycler's `PublishNotifier`, allocated by the reader's first poll.
+/// A reader registers at most one task so re-polling replaces its waker instead of adding another.
+#[derive(Debug, Default)]
+pub(crate) struct WakerSlot(Option<usize>);
+
+/// The wakers of the tasks waiting for the next publish, indexed by `WakerSlot`.
+#[derive(Debug, Default)]
+struct Wakers {
+    /// The registered waker of each slot, taken when woken.
+    slots: Vec<Option<Waker>>,
+    /// Slots of readers that have unregistered, reused before adding more.
+    free: Vec<usize>,
+}
+
+/// Tracks the latest version published by the writer and wakes the tasks and threads waiting for the next one.
+#[derive(Debug, Default)]
+pub(crate) struct PublishNotifier {
+    /// The latest published version, 0 until the writer first publishes.
+    latest_version: AtomicU64,
+    /// Tasks waiting for the next publish.
+    wakers: Mutex<Wakers>,
+    /// Threads waiting for the next publish, parked on the `wakers` mutex.
+    parked: Condvar,
+    /// The amount of registered tasks and parked threads, `notify` skips the mutex while it is 0.
+    waiting: AtomicUsize,
+}
+impl PublishNotifier {
+    /// Gets the latest published version.
+    #[inline]
+    pub(crate) fn latest_version(&self) -> u64 {
+        self.latest_version.load(Ordering::Acquire)
+    }
+
+    /// Records the publish of `version` and wakes every registered task and parked thread.
+    /// Must be called after the newly published index has been stored.
+    ///
+    /// The version is stored and `waiting` loaded with `SeqCst` while waiters count themselves then check the version with `SeqCst`.
+    /// Either the waiter sees the version and doesn't wait, or this sees the waiter and takes the mutex to wake it.
+    pub(crate) fn notify(&self, version: u64) {
+        self.latest_version.store(version, Ordering::SeqCst);
+        if self.waiting.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let wakers: Vec<_> = {
+            let mut wakers = self.wakers.lock();
+            let woken: Vec<_> = wakers.slots.iter_mut().filter_map(Option::take).collect();
+            self.waiting.fetch_sub(woken.len(), Ordering::Relaxed);
+            self.parked.notify_all();
+            woken
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns true if a version newer than `seen` has been published.
+    /// Otherwise registers the task in `cx` in `slot` to be woken by the next publish, replacing the task registered by the last poll.
+    pub(crate) fn poll_newer_than(&self, slot: &mut WakerSlot, seen: u64, cx: &mut Context<'_>) -> bool {
+        if self.latest_version() > seen {
+            return true;
+        }
+        let mut wakers = self.wakers.lock();
+        // Counted then checked again so a publish between the first check and registering either is seen here or wakes the task.
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        if self.latest_version.load(Ordering::SeqCst) > seen {
+            self.waiting.fetch_sub(1, Ordering::Relaxed);
+            return true;
+        }
+        let index = *slot.0.get_or_insert_with(|| match wakers.free.pop() {
+            Some(index) => index,
+            None => {
+                wakers.slots.push(None);
+                wakers.slots.len() - 1
+            }
+        });
+        match &mut wakers.slots[index] {
+            Some(waker) => {
+                waker.clone_from(cx.waker());
+                self.waiting.fetch_sub(1, Ordering::Relaxed);
+            }
+            waker => *waker = Some(cx.waker().clone()),
+        }
+        false
+    }
+
+    /// Removes the task registered in `slot` and frees the slot, for when the future polling it or the reader is dropped.
+    pub(crate) fn unregister(&self, slot: &mut WakerSlot) {
+        if let Some(index) = slot.0.take() {
+            let mut wakers = self.wakers.lock();
+            if wakers.slots[index].take().is_some() {
+                self.waiting.fetch_sub(1, Ordering::Relaxed);
+            }
+            wakers.free.push(index);
+        }
+    }
+
+    /// Gets the amount of registered tasks and parked threads.
+    #[cfg(test)]
+    pub(crate) fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    /// Parks the current thread until a version newer than `seen` has been published or `deadline` passes.
+    /// A `deadline` of `None` waits forever.
+    /// Returns true if a version newer than `seen` has been published.
+    pub(crate) fn wait_newer_than(&self, seen: u64, deadline: Option<Instant>) -> bool {
+        if self.latest_version() > seen {
+            return true;
+        }
+        let mut wakers = self.wakers.lock();
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let mut newer = self.latest_version.load(Ordering::SeqCst) > seen;
+        while !newer {
+            let timed_out = match deadline {
+                Some(deadline) => self.parked.wait_until(&mut wakers, deadline).timed_out(),
+                None => {
+                    self.parked.wait(&mut wakers);
+                    false
+                }
+            };
+            newer = self.latest_version() > seen;
+            if timed_out {
+                break;
+            }
+        }
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+        newer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn waiting_count_test() {
+        let notifier = Arc::new(PublishNotifier::default());
+        let mut slot = WakerSlot::default();
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(!notifier.poll_newer_than(&mut slot, 0, &mut cx));
+        assert_eq!(notifier.waiting(), 1);
+        notifier.notify(1);
+        assert_eq!(notifier.waiting(), 0);
+        assert!(notifier.poll_newer_than(&mut slot, 0, &mut cx));
+        assert!(!notifier.wait_newer_than(1, Some(Instant::now() + Duration::from_millis(10))));
+        assert_eq!(notifier.waiting(), 0);
+
+        let handle = {
+            let notifier = notifier.clone();
+            thread::spawn(move || notifier.wait_newer_than(1, None))
+        };
+        while notifier.waiting() == 0 {
+            thread::yield_now();
+        }
+        notifier.notify(2);
+        assert!(handle.join().unwrap());
+        assert_eq!(notifier.waiting(), 0);
+    }
+
+    #[test]
+    fn waker_slot_test() {
+        let notifier = PublishNotifier::default();
+        let mut slots = [WakerSlot::default(), WakerSlot::default()];
+        let mut cx = Context::from_waker(Waker::noop());
+        for _ in 0..10 {
+            for slot in &mut slots {
+                assert!(!notifier.poll_newer_than(slot, 0, &mut cx));
+            }
+        }
+        // Re-polling replaces each reader's waker.
+        assert_eq!(notifier.wakers.lock().slots.len(), 2);
+        assert_eq!(notifier.waiting(), 2);
+
+        notifier.unregister(&mut slots[0]);
+        assert_eq!(notifier.waiting(), 1);
+        notifier.unregister(&mut slots[0]);
+        assert_eq!(notifier.waiting(), 1);
+
+        // A freed slot is reused.
+        let mut slot = WakerSlot::default();
+        assert!(!notifier.poll_newer_than(&mut slot, 0, &mut cx));
+        assert_eq!(notifier.wakers.lock().slots.len(), 2);
+        notifier.notify(1);
+        assert_eq!(notifier.waiting(), 0);
+        assert!(notifier.wakers.lock().slots.iter().all(Option::is_none));
+    }
+}
@@ -1,38 +1,63 @@
 //! The `RwLockCycler` uses `parking_lot::RwLock` to keep track of reading and writing to each block without using unsafe code.
+//! The cycler and each slot are shared through `Arc`s and held with owned guards, so everything is freed once the writer and all readers are dropped.
 //! This is the first implementation but may not be the fastest as locks are required for each switch.
 //! Due to the way the data structure is designed there is always an available slot so all lock obtainment are use the try variant.
 
-mod builder;
-mod reader;
-mod writer;
-
-pub use builder::{build_multiple_reader, build_single_reader};
-pub use reader::RwLockCyclerReader;
-pub use writer::RwLockCyclerWriter;
-
+use crate::builder::CyclerBackend;
+use crate::error::CyclerBuildError;
+use crate::generic::{self, GenericCyclerReader, GenericCyclerWriter};
+use crate::slot::Slot;
 use crate::traits::{EnsureSend, EnsureSync};
 use parking_lot::RwLock;
-use std::sync::atomic::AtomicU8;
 
-#[derive(Debug)]
-struct RwLockCycler<T> {
-    data_slots: Box<[RwLock<T>]>,
-    most_up_to_date: AtomicU8,
+/// The writer to an `RwLockCycler`
+pub type RwLockCyclerWriter<T> = GenericCyclerWriter<T, RwLock<Slot<T>>>;
+/// The reader for an `RwLockCycler`
+pub type RwLockCyclerReader<T> = GenericCyclerReader<T, RwLock<Slot<T>>>;
+
+/// Selects the `RwLockCycler` in `CyclerBuilder`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RwLockBackend;
+impl CyclerBackend for RwLockBackend {
+    type Storage<T> = RwLock<Slot<T>>;
 }
-impl<T> RwLockCycler<T> {
-    fn num_readers(&self) -> usize {
-        self.data_slots.len() - 2
-    }
+
+impl<T> EnsureSend for RwLockCyclerWriter<T> where T: Send + Sync {}
+impl<T> EnsureSync for RwLockCyclerWriter<T> where T: Send + Sync {}
+impl<T> EnsureSend for RwLockCyclerReader<T> where T: Send + Sync {}
+impl<T> EnsureSync for RwLockCyclerReader<T> where T: Send + Sync {}
+
+/// Creates a single reader RwLockCycler using `values` as the initial values for the slots.
+pub fn build_single_reader<T>(values: [T; 3]) -> (RwLockCyclerWriter<T>, RwLockCyclerReader<T>) {
+    generic::build_single_reader(values)
+}
+
+/// Creates a multi reader RwLockCycler, the amount of readers being `initial_values.len() - 2`.
+pub fn build_multiple_reader<T>(initial_values: Vec<T>) -> (RwLockCyclerWriter<T>, Vec<RwLockCyclerReader<T>>) {
+    generic::build_multiple_reader(initial_values)
+}
+
+/// Creates a single reader RwLockCycler using `values` as the initial values for the slots.
+pub fn try_build_single_reader<T>(values: [T; 3]) -> Result<(RwLockCyclerWriter<T>, RwLockCyclerReader<T>), CyclerBuildError> {
+    generic::try_build_single_reader(values)
+}
+
+/// Creates a multi reader RwLockCycler, the amount of readers being `initial_values.len() - 2`.
+/// Fails if there are fewer than 3 initial values or more than the cycler can have slots.
+pub fn try_build_multiple_reader<T>(initial_values: Vec<T>) -> Result<(RwLockCyclerWriter<T>, Vec<RwLockCyclerReader<T>>), CyclerBuildError> {
+    generic::try_build_multiple_reader(initial_values)
 }
-impl<T> EnsureSend for RwLockCycler<T> where T: Send {}
-impl<T> EnsureSync for RwLockCycler<T> where T: Send + Sync {}
 
 #[cfg(test)]
 mod test {
-    use crate::rw_lock_cycler::build_single_reader;
-    use crate::test::TestData;
+    use crate::error::CyclerBuildError;
+    use crate::rw_lock_cycler::{build_multiple_reader, build_single_reader, try_build_multiple_reader, try_build_single_reader, RwLockBackend};
+    use crate::slot_table::MAX_SLOTS;
+    use crate::testing::{run_backend_suite, TestData};
     use crate::traits::{CyclerReader, CyclerWriterDefault, ReadAccess, WriteAccess};
     use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
     #[test]
     fn default_test() {
         let (mut writer, mut reader) =
@@ -54,4 +79,66 @@ mod test {
         reader.read_latest();
         assert_eq!(*reader.read_data(), new_data);
     }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn stream_test() {
+        use crate::testing::block_on;
+        use crate::ReaderStream;
+        use futures_core::Stream;
+        use std::pin::Pin;
+        use std::thread;
+
+        let (mut writer, reader) =
+            build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+        let mut stream = ReaderStream::new(reader);
+        let handle = thread::spawn(move || {
+            let mut last_version = 0;
+            while stream.reader().read_data().test1 != 3 {
+                let version = block_on(std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))).unwrap();
+                assert!(version > last_version);
+                last_version = version;
+            }
+        });
+        for count in 1..=3 {
+            writer.write_data_mut().test1 = count;
+            writer.write_next();
+        }
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn drop_test() {
+        let (writer, readers) = build_multiple_reader(vec![TestData::default(); 4]);
+        let added = writer.add_reader().unwrap();
+        let cycler = Arc::downgrade(&writer.cycler);
+        let slots: Vec<_> = (0..writer.cycler.data_slots.len()).map(|index| Arc::downgrade(&writer.cycler.data_slots[index])).collect();
+        drop(writer);
+        drop(readers);
+        assert!(cycler.upgrade().is_some());
+        drop(added);
+        // Everything is freed once the writer and all readers are dropped.
+        assert!(cycler.upgrade().is_none());
+        assert!(slots.iter().all(|slot| slot.upgrade().is_none()));
+    }
+
+    #[test]
+    fn suite_test() {
+        run_backend_suite::<RwLockBackend>();
+    }
+
+    #[test]
+    fn build_error_test() {
+        assert_eq!(try_build_multiple_reader(vec![TestData::default(); 2]).unwrap_err(), CyclerBuildError::TooFewSlots { slots: 2 });
+        assert_eq!(try_build_multiple_reader(Vec::<TestData>::new()).unwrap_err().to_string(), "a cycler needs at least 3 slots, 0 were given");
+        assert!(try_build_single_reader([0, 0, 0]).is_ok());
+
+        // Used to overflow computing the amount of slots.
+        let (_writer, readers) = crate::build_multiple_reader_default::<usize>(u8::MAX as usize);
+        assert_eq!(readers.len(), 255);
+        assert_eq!(crate::try_build_multiple_reader_default::<usize>(0).unwrap_err(), CyclerBuildError::TooFewSlots { slots: 2 });
+        let error = crate::try_build_multiple_reader_cloned(0, usize::MAX).unwrap_err();
+        assert_eq!(error, CyclerBuildError::TooManyReaders { readers: usize::MAX, max_readers: MAX_SLOTS - 2 });
+        assert_eq!(error.to_string(), format!("a cycler can have at most {} readers, {} were asked for", MAX_SLOTS - 2, usize::MAX));
+    }
 }
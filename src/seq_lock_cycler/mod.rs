@@ -0,0 +1,546 @@
+//! The `SeqLockCycler` is a cycler for small `Copy` data that keeps a single shared block behind a sequence lock instead of a slot per reader.
+//! Every reader keeps its own copy of the data and the writer two it alternates between, the writer copies its block in to publish and readers copy the latest block out.
+//! A reader copies optimistically while the writer may be writing and retries if the sequence shows the copy was torn, so neither side ever takes a lock.
+//! There is no limit to the amount of readers, they are cloned freely and take no memory in the cycler.
+//!
+//! The shared block is copied word by word through atomics so a copy racing the writer is only torn, never a data race.
+//! Data must implement `SeqLockData`, promising it has no padding as uninitialized bytes can't be copied into an integer.
+//!
+//! ## Memory ordering
+//! The sequence is odd while the writer is copying in, and `2 * version` once the block of `version` is published.
+//! The writer stores the odd sequence, fences with `Release`, stores the words `Relaxed` and stores the even sequence with `Release`.
+//! The reader loads the sequence with `Acquire`, loads the words `Relaxed` and fences with `Acquire` before loading the sequence again.
+//! If the reader loaded any word the writer stored after the odd sequence, the fences make the second load see at least that sequence, so a copy is only kept when the sequence didn't change.
+
+use crate::notifier::{PublishNotifier, WakerSlot};
+use crate::slot::CloneContext;
+use crate::sync::{fence, spin_loop, AtomicU64, AtomicUsize};
+use crate::traits::*;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::mem::{size_of, MaybeUninit};
+use std::ptr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Data that can be stored in a `SeqLockCycler`, copied in and out through atomic words.
+///
+/// # Safety
+/// Every byte of the type must be initialized, it can't have padding or contain `MaybeUninit`.
+/// Implemented for primitives and arrays of them, structs of them can implement it if they are `#[repr(C)]` with no padding between or after fields.
+pub unsafe trait SeqLockData: Copy {}
+macro_rules! impl_seq_lock_data {
+    ($($ty:ty),*) => {
+        $(unsafe impl SeqLockData for $ty {})*
+    };
+}
+impl_seq_lock_data!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char, ());
+unsafe impl<T, const N: usize> SeqLockData for [T; N] where T: SeqLockData {}
+
+/// The bytes of `T` in each word, the last word holding the rest.
+#[inline]
+fn word_bytes<T>(word: usize) -> usize {
+    (size_of::<T>() - word * size_of::<usize>()).min(size_of::<usize>())
+}
+
+/// The shared block and its sequence.
+struct SeqLock<T> {
+    /// Odd while the writer is copying into `words`, otherwise twice the version of the block in them.
+    seq: AtomicU64,
+    /// The bytes of the block, the bytes after the end of the block in the last word are zero.
+    words: Box<[AtomicUsize]>,
+    notifier: PublishNotifier,
+    _data: PhantomData<T>,
+}
+impl<T> SeqLock<T> where T: SeqLockData {
+    fn new(value: &T) -> Self {
+        let words = (0..size_of::<T>().div_ceil(size_of::<usize>())).map(|_| AtomicUsize::new(0)).collect();
+        let lock = Self { seq: AtomicU64::new(0), words, notifier: PublishNotifier::default(), _data: PhantomData };
+        lock.store_words(value);
+        lock
+    }
+
+    /// Copies `value` into the words.
+    fn store_words(&self, value: &T) {
+        let bytes = value as *const T as *const u8;
+        for (index, word) in self.words.iter().enumerate() {
+            let mut value = 0usize;
+            // Safety: the range is within `T` whose bytes are all initialized, and within `value`.
+            unsafe { ptr::copy_nonoverlapping(bytes.add(index * size_of::<usize>()), &mut value as *mut usize as *mut u8, word_bytes::<T>(index)) };
+            word.store(value, Ordering::Relaxed);
+        }
+    }
+
+    /// Copies the words out, the result is only a valid `T` if the writer didn't store during the copy.
+    fn load_words(&self) -> MaybeUninit<T> {
+        let mut data = MaybeUninit::<T>::uninit();
+        let bytes = data.as_mut_ptr() as *mut u8;
+        for (index, word) in self.words.iter().enumerate() {
+            let value = word.load(Ordering::Relaxed);
+            // Safety: the range is within `T` and within `value`.
+            unsafe { ptr::copy_nonoverlapping(&value as *const usize as *const u8, bytes.add(index * size_of::<usize>()), word_bytes::<T>(index)) };
+        }
+        data
+    }
+
+    /// Copies `value` in as the block of `version`, only called by the writer.
+    fn store(&self, value: &T, version: u64) {
+        self.seq.store(version * 2 - 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.store_words(value);
+        self.seq.store(version * 2, Ordering::Release);
+        self.notifier.notify(version);
+    }
+
+    /// Copies out the latest block if it is newer than `version`, returning it and its version.
+    fn load_newer_than(&self, version: u64) -> Option<(T, u64)> {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            // While the writer copies in the block after `version` the latest published is still `version`.
+            if seq / 2 == version {
+                return None;
+            }
+            if seq & 1 == 0 {
+                let data = self.load_words();
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == seq {
+                    // Safety: the sequence didn't change so every word is from the block of `seq`, which was a valid `T`.
+                    return Some((unsafe { data.assume_init() }, seq / 2));
+                }
+            }
+            spin_loop();
+        }
+    }
+}
+
+/// The writer to a `SeqLockCycler`
+/// Like a cycler with two slots, the writer alternates between two blocks so the block cloned into is the one published before the last.
+pub struct SeqLockCyclerWriter<T> {
+    lock: Arc<SeqLock<T>>,
+    /// The block being written and the block published before it.
+    blocks: [T; 2],
+    /// The index of the block being written.
+    index: usize,
+}
+impl<T> SeqLockCyclerWriter<T> {
+    #[inline]
+    fn block(&self) -> &T {
+        &self.blocks[self.index]
+    }
+
+    #[inline]
+    fn block_mut(&mut self) -> &mut T {
+        &mut self.blocks[self.index]
+    }
+
+    /// Switches to the other block, returning it and the block that was being written.
+    fn switch(&mut self) -> (&mut T, &mut T) {
+        self.index ^= 1;
+        let [first, second] = &mut self.blocks;
+        if self.index == 0 {
+            (first, second)
+        } else {
+            (second, first)
+        }
+    }
+}
+impl<T> SeqLockCyclerWriter<T> where T: SeqLockData {
+    /// Publishes the current block then switches to the other, calling `clone_fn` with the other block and the published one.
+    /// Returns the version published.
+    fn publish(&mut self, clone_fn: impl FnOnce(&mut T, &T)) -> u64 {
+        let version = self.lock.notifier.latest_version() + 1;
+        self.lock.store(self.block(), version);
+        let (to, from) = self.switch();
+        clone_fn(to, from);
+        version
+    }
+
+    /// Switches to the other block calling `clone_fn` with it and the current block, then publishes the current block.
+    fn publish_mut(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64 {
+        let version = self.lock.notifier.latest_version() + 1;
+        let (to, from) = self.switch();
+        clone_fn(to, from);
+        let published = *from;
+        self.lock.store(&published, version);
+        version
+    }
+
+    /// Publishes calling `clone_fn` with a `CloneContext`.
+    /// The block cloned into was published as the version before, or is the initial block of version 0.
+    fn publish_context(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64 {
+        let version = self.lock.notifier.latest_version() + 1;
+        let slot = self.index ^ 1;
+        self.publish(|to, from| clone_fn(to, from, CloneContext { target_version: version - 1, source_version: version, slot }))
+    }
+}
+impl<T> Debug for SeqLockCyclerWriter<T> where T: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SeqLockCyclerWriter").field("index", &self.index).field("block", self.block()).finish()
+    }
+}
+impl<T> ReadAccess for SeqLockCyclerWriter<T> where T: ReadAccess {
+    type Read = T::Read;
+
+    /// Gets a shared reference to the read data of the current block
+    #[inline]
+    fn read_data(&self) -> &Self::Read {
+        self.block().read_data()
+    }
+}
+impl<T> WriteAccess for SeqLockCyclerWriter<T> where T: WriteAccess {
+    type Write = T::Write;
+
+    /// Gets a shared reference to the write data of the current block
+    #[inline]
+    fn write_data(&self) -> &Self::Write {
+        self.block().write_data()
+    }
+
+    /// Gets an exclusive reference to the write data of the current block
+    #[inline]
+    fn write_data_mut(&mut self) -> &mut Self::Write {
+        self.block_mut().write_data_mut()
+    }
+}
+impl<T> CyclerWriter<T> for SeqLockCyclerWriter<T> where T: SeqLockData + WriteAccess {}
+impl<T> CyclerWriterFn<T> for SeqLockCyclerWriter<T> where T: SeqLockData + WriteAccess {
+    fn write_next_fn(&mut self, clone_fn: fn(&mut T, &T)) -> u64 {
+        self.publish(clone_fn)
+    }
+
+    fn write_next_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T)) -> u64 where Self: Sized {
+        self.publish(clone_fn)
+    }
+
+    fn write_next_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T)) -> u64 {
+        self.publish(clone_fn)
+    }
+
+    fn write_next_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T)>) -> u64 {
+        self.publish(clone_fn)
+    }
+}
+impl<T> CyclerWriterMutFn<T> for SeqLockCyclerWriter<T> where T: SeqLockData + WriteAccess {
+    fn write_next_mut_fn(&mut self, clone_fn: fn(&mut T, &mut T)) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64 where Self: Sized {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &mut T)) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &mut T)>) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+}
+impl<T> CyclerWriterDefault<T> for SeqLockCyclerWriter<T> where T: SeqLockData + WriteAccess {
+    fn write_next(&mut self) -> u64 {
+        self.publish(|to, from| *to = *from)
+    }
+}
+impl<T> CyclerWriterContextFn<T> for SeqLockCyclerWriter<T> where T: SeqLockData + WriteAccess {
+    fn write_next_context_fn(&mut self, clone_fn: fn(&mut T, &T, CloneContext)) -> u64 {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64 where Self: Sized {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T, CloneContext)) -> u64 {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T, CloneContext)>) -> u64 {
+        self.publish_context(clone_fn)
+    }
+}
+
+/// The reader for a `SeqLockCycler`
+pub struct SeqLockCyclerReader<T> {
+    lock: Arc<SeqLock<T>>,
+    /// The copy of the block being read.
+    data: T,
+    version: u64,
+    waker: WakerSlot,
+}
+impl<T> Clone for SeqLockCyclerReader<T> where T: SeqLockData {
+    /// Creates a reader reading the same block, there is no limit to the amount of readers.
+    fn clone(&self) -> Self {
+        Self { lock: self.lock.clone(), data: self.data, version: self.version, waker: WakerSlot::default() }
+    }
+}
+impl<T> Drop for SeqLockCyclerReader<T> {
+    fn drop(&mut self) {
+        self.lock.notifier.unregister(&mut self.waker);
+    }
+}
+impl<T> Debug for SeqLockCyclerReader<T> where T: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SeqLockCyclerReader").field("version", &self.version).field("data", &self.data).finish()
+    }
+}
+impl<T> ReadAccess for SeqLockCyclerReader<T> where T: ReadAccess {
+    type Read = T::Read;
+
+    #[inline]
+    fn read_data(&self) -> &Self::Read {
+        self.data.read_data()
+    }
+}
+impl<T> CyclerReader<T> for SeqLockCyclerReader<T> where T: SeqLockData + ReadAccess {
+    /// Retries the copy until the writer doesn't write during it, so a writer publishing without pause can delay this.
+    fn read_latest(&mut self) -> bool {
+        match self.lock.load_newer_than(self.version) {
+            Some((data, version)) => {
+                self.data = data;
+                self.version = version;
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn version(&self) -> u64 {
+        self.version
+    }
+}
+impl<T> CyclerReaderAsync<T> for SeqLockCyclerReader<T> where T: SeqLockData + ReadAccess {
+    fn poll_read_latest(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.lock.notifier.poll_newer_than(&mut self.waker, self.version, cx) {
+            self.read_latest();
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn cancel_read_latest(&mut self) {
+        self.lock.notifier.unregister(&mut self.waker);
+    }
+}
+impl<T> CyclerReaderWait<T> for SeqLockCyclerReader<T> where T: SeqLockData + ReadAccess {
+    fn wait_for_update(&self, timeout: Duration) -> bool {
+        self.lock.notifier.wait_newer_than(self.version, Instant::now().checked_add(timeout))
+    }
+
+    fn wait_for_update_until(&self, deadline: Instant) -> bool {
+        self.lock.notifier.wait_newer_than(self.version, Some(deadline))
+    }
+}
+
+impl<T> EnsureSend for SeqLockCyclerWriter<T> where T: SeqLockData + Send + Sync {}
+impl<T> EnsureSync for SeqLockCyclerWriter<T> where T: SeqLockData + Send + Sync {}
+impl<T> EnsureSend for SeqLockCyclerReader<T> where T: SeqLockData + Send + Sync {}
+impl<T> EnsureSync for SeqLockCyclerReader<T> where T: SeqLockData + Send + Sync {}
+
+/// Creates a single reader SeqLockCycler with the reader and writer starting on `initial_value`.
+/// More readers are created by cloning the reader.
+pub fn build_single_reader<T>(initial_value: T) -> (SeqLockCyclerWriter<T>, SeqLockCyclerReader<T>) where T: SeqLockData {
+    let lock = Arc::new(SeqLock::new(&initial_value));
+    (SeqLockCyclerWriter { lock: lock.clone(), blocks: [initial_value; 2], index: 0 }, SeqLockCyclerReader { lock, data: initial_value, version: 0, waker: WakerSlot::default() })
+}
+
+/// Creates a SeqLockCycler with `num_readers` readers, all starting on `initial_value`.
+pub fn build_multiple_reader<T>(initial_value: T, num_readers: usize) -> (SeqLockCyclerWriter<T>, Vec<SeqLockCyclerReader<T>>) where T: SeqLockData {
+    let (writer, reader) = build_single_reader(initial_value);
+    (writer, vec![reader; num_readers])
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::seq_lock_cycler::{build_multiple_reader, build_single_reader, SeqLockData};
+    use crate::testing::block_on;
+    use crate::{CloneContext, CyclerReader, CyclerReaderAsync, CyclerReaderWait, CyclerWriterContextFn, CyclerWriterDefault, CyclerWriterFn, CyclerWriterMutFn, ReadAccess, UniversalCyclerReader, UniversalCyclerWriter, WriteAccess};
+    use std::thread;
+    use std::time::Duration;
+
+    /// `Copy` data of `N` words, a torn copy shows as differing values.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    #[repr(C)]
+    pub(crate) struct Block<const N: usize = 16> {
+        pub values: [usize; N],
+    }
+    impl<const N: usize> Block<N> {
+        pub(crate) fn new(value: usize) -> Self {
+            Self { values: [value; N] }
+        }
+    }
+    impl<const N: usize> Default for Block<N> {
+        fn default() -> Self {
+            Self::new(0)
+        }
+    }
+    // Safety: a `repr(C)` struct of one array of `usize` has no padding.
+    unsafe impl<const N: usize> SeqLockData for Block<N> {}
+    impl<const N: usize> ReadAccess for Block<N> {
+        type Read = Self;
+
+        fn read_data(&self) -> &Self::Read {
+            self
+        }
+    }
+    impl<const N: usize> WriteAccess for Block<N> {
+        type Write = Self;
+
+        fn write_data(&self) -> &Self::Write {
+            self
+        }
+
+        fn write_data_mut(&mut self) -> &mut Self::Write {
+            self
+        }
+    }
+
+    #[test]
+    fn default_test() {
+        let (mut writer, mut reader) = build_single_reader(<Block>::default());
+        assert_eq!(reader.version(), 0);
+        assert!(!reader.read_latest());
+        *writer.write_data_mut() = Block::new(1);
+        assert_eq!(writer.write_next(), 1);
+        // The writer keeps writing a copy of the block it published.
+        assert_eq!(*writer.write_data(), Block::new(1));
+        assert_eq!(*reader.read_data(), Block::default());
+        assert!(reader.wait_for_update(Duration::ZERO));
+        assert!(reader.read_latest());
+        assert_eq!((reader.version(), *reader.read_data()), (1, Block::new(1)));
+        assert!(!reader.read_latest());
+        assert!(!reader.wait_for_update(Duration::ZERO));
+
+        // Readers are cloned on the block they were cloned from.
+        let mut cloned = reader.clone();
+        *writer.write_data_mut() = Block::new(2);
+        writer.write_next();
+        *writer.write_data_mut() = Block::new(3);
+        writer.write_next();
+        assert!(cloned.read_latest());
+        assert_eq!((cloned.version(), *cloned.read_data()), (3, Block::new(3)));
+        assert_eq!(reader.version(), 1);
+        drop(writer);
+        assert!(reader.read_latest());
+        assert_eq!(reader.version(), 3);
+    }
+
+    #[test]
+    fn clone_fn_test() {
+        let (mut writer, mut reader) = build_single_reader(<Block>::default());
+        writer.write_data_mut().values[0] = 1;
+        // The block cloned into is the initial block, the writer alternates between two.
+        assert_eq!(writer.write_next_fn(|to, from| {
+            assert_eq!(to.values[0], 0);
+            *to = *from;
+            to.values[1] = 5;
+        }), 1);
+        assert_eq!(writer.write_data().values[..2], [1, 5]);
+        reader.read_latest();
+        assert_eq!(reader.read_data().values[..2], [1, 0]);
+
+        // The mutable clone function may change the block before it is published.
+        assert_eq!(writer.write_next_mut_fn(|to, from| {
+            assert_eq!(to.values[..2], [1, 0]);
+            from.values[2] = 7;
+            *to = *from;
+            to.values[3] = 9;
+        }), 2);
+        assert_eq!(writer.write_data().values[..4], [1, 5, 7, 9]);
+        reader.read_latest();
+        assert_eq!(reader.read_data().values[..4], [1, 5, 7, 0]);
+
+        // The block cloned into was published as the version before the one being copied.
+        let mut contexts = Vec::new();
+        for version in 3..=4 {
+            assert_eq!(writer.write_next_context_fn_impl(|to, from, context| {
+                assert_eq!(to.values[..4], [1, 5, 7, if version == 3 { 0 } else { 9 }]);
+                *to = *from;
+                contexts.push(context);
+            }), version);
+        }
+        assert_eq!(contexts, [CloneContext { target_version: 2, source_version: 3, slot: 1 }, CloneContext { target_version: 3, source_version: 4, slot: 0 }]);
+    }
+
+    #[test]
+    fn universal_test() {
+        let (mut writer, mut reader) = build_single_reader(<Block>::default());
+        writer.write_data_mut().values[0] = 1;
+        writer.write_next();
+        block_on(reader.read_latest_async());
+        assert_eq!((reader.version(), reader.read_data().values[0]), (1, 1));
+        let writer: &mut dyn UniversalCyclerWriter<Block> = &mut writer;
+        let reader: &mut dyn UniversalCyclerReader<Block> = &mut reader;
+        writer.write_data_mut().values[0] = 2;
+        assert_eq!(writer.write_next_mut_fn_dyn(&mut |to, from| to.clone_from(from)), 2);
+        writer.write_data_mut().values[0] = 3;
+        assert_eq!(writer.write_next_fn_dyn_boxed(Box::new(|to, from| to.clone_from(from))), 3);
+        assert!(reader.wait_for_update(Duration::ZERO));
+        assert!(reader.read_latest());
+        assert_eq!((reader.version(), reader.read_data().values[0]), (3, 3));
+    }
+
+    #[test]
+    fn torn_read_test() {
+        let (mut writer, readers) = build_multiple_reader(<Block>::default(), if cfg!(miri) { 2 } else { 8 });
+        let publishes = if cfg!(miri) { 10 } else { 10_000 };
+        let handles: Vec<_> = readers
+            .into_iter()
+            .map(|mut reader| {
+                thread::spawn(move || {
+                    let mut last_version = 0;
+                    while last_version < publishes as u64 {
+                        reader.read_latest();
+                        let version = reader.version();
+                        assert!(version >= last_version, "Reader went from version {} to {}", last_version, version);
+                        assert_eq!(*reader.read_data(), Block::new(version as usize));
+                        last_version = version;
+                    }
+                })
+            })
+            .collect();
+        for version in 1..=publishes {
+            *writer.write_data_mut() = Block::new(version);
+            writer.write_next();
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use crate::seq_lock_cycler::build_single_reader;
+    use crate::seq_lock_cycler::test::Block;
+    use crate::{CyclerReader, CyclerWriterDefault, CyclerWriterMutFn, ReadAccess, WriteAccess};
+    use loom::thread;
+
+    #[test]
+    fn loom_publish_test() {
+        loom::model(|| {
+            let (mut writer, mut reader) = build_single_reader(Block::<2>::default());
+            let writer = thread::spawn(move || {
+                *writer.write_data_mut() = Block::new(1);
+                writer.write_next();
+                *writer.write_data_mut() = Block::new(2);
+                writer.write_next_mut_fn(|to, from| *to = *from);
+            });
+            // Every word goes through an atomic so loom checks which store each copy sees, a torn copy must never be kept.
+            let mut last_version = 0;
+            for _ in 0..2 {
+                reader.read_latest();
+                assert!(reader.version() >= last_version);
+                assert_eq!(*reader.read_data(), Block::new(reader.version() as usize));
+                last_version = reader.version();
+            }
+            writer.join().unwrap();
+            reader.read_latest();
+            assert_eq!((reader.version(), *reader.read_data()), (2, Block::new(2)));
+        });
+    }
+}
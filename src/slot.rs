@@ -0,0 +1,72 @@
+//! The slot type stored in each lock of a cycler and the context given to clone functions about it.
+
+use crate::cache_padded::TrailingPadding;
+use std::fmt::{Debug, Formatter};
+
+/// A block of data along with the version it was last published as.
+/// The version is only changed by the writer while it holds the slot write locked, so it always matches the data readers see.
+/// This is the value a `SlotLock` of a cycler holding `T` protects.
+///
+/// Slots end in a cache line of padding so the lock of the next slot, written by every reader switching to it, is never on a cache line of this one.
+/// The padding is at the end rather than an alignment as the lock and its `Arc` are allocated by the `SlotLock`.
+pub struct Slot<T> {
+    /// The version this slot was last published as, 0 if it has never been published.
+    pub(crate) version: u64,
+    /// Only `None` while the slot is retired, write locked by the slot table and never read.
+    data: Option<T>,
+    _padding: TrailingPadding,
+}
+impl<T> Slot<T> {
+    pub(crate) fn new(data: T) -> Self {
+        Self { version: 0, data: Some(data), _padding: TrailingPadding::default() }
+    }
+
+    #[inline]
+    pub(crate) fn data(&self) -> &T {
+        self.data.as_ref().expect("Retired slot accessed")
+    }
+
+    #[inline]
+    pub(crate) fn data_mut(&mut self) -> &mut T {
+        self.data.as_mut().expect("Retired slot accessed")
+    }
+
+    /// Drops the data of a slot no reader needs anymore.
+    pub(crate) fn retire(&mut self) {
+        self.data = None;
+    }
+
+    /// Refills a retired slot with `data`, as never published.
+    pub(crate) fn revive(&mut self, data: T) {
+        self.version = 0;
+        self.data = Some(data);
+    }
+}
+impl<T> Default for Slot<T> where T: Default {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+impl<T> Debug for Slot<T> where T: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("Slot");
+        debug.field("version", &self.version);
+        match &self.data {
+            Some(data) => debug.field("data", data),
+            None => debug.field("data", &format_args!("<retired>")),
+        };
+        debug.finish()
+    }
+}
+
+/// Information about the blocks involved when the writer moves to the next block.
+/// Given to the clone functions of `CyclerWriterContextFn` so they can catch the next block up incrementally.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CloneContext {
+    /// The version the block being cloned into was last published as, 0 if it has never been published.
+    pub target_version: u64,
+    /// The version of the block being cloned from, this is the version being published.
+    pub source_version: u64,
+    /// The index of the slot being cloned into.
+    pub slot: usize,
+}
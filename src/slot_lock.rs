@@ -0,0 +1,186 @@
+//! The lock abstraction the generic cycler is built on and its implementations.
+//! A cycler only ever uses the try variants of the lock functions as there is always a free slot, so implementations don't need to block.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+#[cfg(feature = "allow_unsafe")]
+pub use std_lock::{StdRwLockReadGuard, StdRwLockWriteGuard};
+
+/// A reader/writer lock around a single slot of a cycler.
+/// Guards are owned, holding the `Arc` of the lock, so readers and the writer can keep them between calls.
+/// Implementing this for a lock gives `GenericCyclerWriter` and `GenericCyclerReader` using it all of the cycler traits.
+pub trait SlotLock<V>: Sized {
+    /// Owned guard of shared access to the value.
+    type ReadGuard: Deref<Target = V>;
+    /// Owned guard of exclusive access to the value.
+    type WriteGuard: DerefMut<Target = V>;
+    /// The most read guards the lock can hand out at once.
+    /// A slot can be read by every reader and the writer at once, so this limits the amount of slots of a cycler to one more than it.
+    const MAX_READERS: usize = usize::MAX;
+
+    /// Creates an unlocked lock holding `value`.
+    fn new(value: V) -> Self;
+
+    /// Tries to get shared access, failing if there is a writer.
+    fn try_read(lock: &Arc<Self>) -> Option<Self::ReadGuard>;
+
+    /// Tries to get exclusive access, failing if there is a reader or writer.
+    fn try_write(lock: &Arc<Self>) -> Option<Self::WriteGuard>;
+
+    /// Turns exclusive access into shared access without letting a writer in between.
+    fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard;
+}
+
+impl<V> SlotLock<V> for parking_lot::RwLock<V> {
+    type ReadGuard = parking_lot::lock_api::ArcRwLockReadGuard<parking_lot::RawRwLock, V>;
+    type WriteGuard = parking_lot::lock_api::ArcRwLockWriteGuard<parking_lot::RawRwLock, V>;
+
+    #[inline]
+    fn new(value: V) -> Self {
+        parking_lot::RwLock::new(value)
+    }
+
+    #[inline]
+    fn try_read(lock: &Arc<Self>) -> Option<Self::ReadGuard> {
+        lock.try_read_arc()
+    }
+
+    #[inline]
+    fn try_write(lock: &Arc<Self>) -> Option<Self::WriteGuard> {
+        lock.try_write_arc()
+    }
+
+    #[inline]
+    fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard {
+        parking_lot::lock_api::ArcRwLockWriteGuard::downgrade(guard)
+    }
+}
+
+#[cfg(feature = "atomic_rw_lock")]
+impl<V, A> SlotLock<V> for crate::atomic_rw_lock::AtomicRwLock<V, A>
+where
+    A: crate::atomic_rw_lock::AtomicValue,
+{
+    type ReadGuard = crate::atomic_rw_lock::AtomicArcReader<V, A>;
+    type WriteGuard = crate::atomic_rw_lock::AtomicArcWriter<V, A>;
+    const MAX_READERS: usize = A::MAX_READERS;
+
+    #[inline]
+    fn new(value: V) -> Self {
+        crate::atomic_rw_lock::AtomicRwLock::new(value)
+    }
+
+    #[inline]
+    fn try_read(lock: &Arc<Self>) -> Option<Self::ReadGuard> {
+        lock.try_read_static()
+    }
+
+    #[inline]
+    fn try_write(lock: &Arc<Self>) -> Option<Self::WriteGuard> {
+        lock.try_write_static()
+    }
+
+    #[inline]
+    fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard {
+        guard.downgrade()
+    }
+}
+
+/// `std::sync::RwLock` has no owned guards so they are made here by holding the `Arc` next to a guard with its lifetime extended.
+/// Like the guards of `std::sync::RwLock` these are not `Send`, so the writer and readers of a cycler using it can't be moved between threads.
+#[cfg(feature = "allow_unsafe")]
+mod std_lock {
+    use crate::slot_lock::SlotLock;
+    use std::fmt::{Debug, Formatter};
+    use std::ops::{Deref, DerefMut};
+    use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+    /// Owned read guard of a `std::sync::RwLock`, not `Send` like the guard it holds.
+    pub struct StdRwLockReadGuard<V: 'static> {
+        // Declared before `_lock` so it is dropped while the lock is still alive.
+        guard: RwLockReadGuard<'static, V>,
+        _lock: Arc<RwLock<V>>,
+    }
+    impl<V> Deref for StdRwLockReadGuard<V> {
+        type Target = V;
+
+        #[inline]
+        fn deref(&self) -> &Self::Target {
+            &self.guard
+        }
+    }
+    impl<V> Debug for StdRwLockReadGuard<V> where V: Debug {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            Debug::fmt(&*self.guard, f)
+        }
+    }
+
+    /// Owned write guard of a `std::sync::RwLock`, not `Send` like the guard it holds.
+    pub struct StdRwLockWriteGuard<V: 'static> {
+        // Declared before `lock` so it is dropped while the lock is still alive.
+        guard: RwLockWriteGuard<'static, V>,
+        lock: Arc<RwLock<V>>,
+    }
+    impl<V> Deref for StdRwLockWriteGuard<V> {
+        type Target = V;
+
+        #[inline]
+        fn deref(&self) -> &Self::Target {
+            &self.guard
+        }
+    }
+    impl<V> DerefMut for StdRwLockWriteGuard<V> {
+        #[inline]
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.guard
+        }
+    }
+    impl<V> Debug for StdRwLockWriteGuard<V> where V: Debug {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            Debug::fmt(&*self.guard, f)
+        }
+    }
+
+    /// Extends the lifetime of a borrow of `lock` to `'static`.
+    /// Safety: the result must be dropped before the last clone of `lock`.
+    unsafe fn extend<V>(lock: &Arc<RwLock<V>>) -> &'static RwLock<V> where V: 'static {
+        &*Arc::as_ptr(lock)
+    }
+
+    impl<V> SlotLock<V> for RwLock<V> where V: 'static {
+        type ReadGuard = StdRwLockReadGuard<V>;
+        type WriteGuard = StdRwLockWriteGuard<V>;
+
+        #[inline]
+        fn new(value: V) -> Self {
+            RwLock::new(value)
+        }
+
+        fn try_read(lock: &Arc<Self>) -> Option<Self::ReadGuard> {
+            // Safety: the guard is stored next to a clone of `lock` and dropped first.
+            let guard = match unsafe { extend(lock) }.try_read() {
+                Ok(guard) => guard,
+                // A panicking writer does not make the slot unusable, the next publish overwrites it.
+                Err(TryLockError::Poisoned(error)) => error.into_inner(),
+                Err(TryLockError::WouldBlock) => return None,
+            };
+            Some(StdRwLockReadGuard { guard, _lock: lock.clone() })
+        }
+
+        fn try_write(lock: &Arc<Self>) -> Option<Self::WriteGuard> {
+            // Safety: the guard is stored next to a clone of `lock` and dropped first.
+            let guard = match unsafe { extend(lock) }.try_write() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(error)) => error.into_inner(),
+                Err(TryLockError::WouldBlock) => return None,
+            };
+            Some(StdRwLockWriteGuard { guard, lock: lock.clone() })
+        }
+
+        fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard {
+            let StdRwLockWriteGuard { guard, lock } = guard;
+            StdRwLockReadGuard { guard: RwLockWriteGuard::downgrade(guard), _lock: lock }
+        }
+    }
+}
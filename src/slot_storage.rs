@@ -0,0 +1,162 @@
+//! How the generic cycler stores its slots, each in its own allocation or all in one.
+//! Every `SlotLock` is a storage keeping each slot in its own `Arc`, `Contiguous` keeps the slots in the slot table, those a cycler is built with in a single allocation.
+
+use crate::slot::Slot;
+use crate::slot_lock::SlotLock;
+use std::sync::Arc;
+
+#[cfg(feature = "atomic_rw_lock")]
+pub use contiguous::Contiguous;
+
+/// How a cycler stores the slots holding `T` and locks them.
+/// Implemented for every `SlotLock` and for `Contiguous`, new locks are added by implementing `SlotLock`.
+pub trait SlotStorage<T>: sealed::Storage<T> {}
+impl<T, S> SlotStorage<T> for S where S: sealed::Storage<T> {}
+
+pub(crate) mod sealed {
+    use crate::slot::Slot;
+    use std::ops::{Deref, DerefMut};
+
+    /// The functions of `SlotStorage`, private so only the generic cycler can take guards to its slots.
+    /// Guards may borrow the slot table, the generic cycler drops them before its `Arc` of the cycler.
+    pub trait Storage<T>: Sized {
+        /// What the cycler keeps for each slot, guards are taken through it.
+        type Handle;
+        /// Owned guard of shared access to a slot.
+        type ReadGuard: Deref<Target = Slot<T>>;
+        /// Owned guard of exclusive access to a slot.
+        type WriteGuard: DerefMut<Target = Slot<T>>;
+        /// The most read guards a slot can hand out at once.
+        const MAX_READERS: usize;
+
+        /// Creates the slots a cycler is built with, in order.
+        fn new_slots(values: Vec<T>) -> Vec<Self::Handle>;
+
+        /// Creates a slot added after the cycler is built.
+        fn new_slot(value: T) -> Self::Handle;
+
+        /// Tries to get shared access, failing if there is a writer.
+        fn try_read(slot: &Self::Handle) -> Option<Self::ReadGuard>;
+
+        /// Tries to get exclusive access, failing if there is a reader or writer.
+        fn try_write(slot: &Self::Handle) -> Option<Self::WriteGuard>;
+
+        /// Turns exclusive access into shared access without letting a writer in between.
+        fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard;
+    }
+}
+
+impl<T, L> sealed::Storage<T> for L where L: SlotLock<Slot<T>> {
+    type Handle = Arc<L>;
+    type ReadGuard = L::ReadGuard;
+    type WriteGuard = L::WriteGuard;
+    const MAX_READERS: usize = L::MAX_READERS;
+
+    fn new_slots(values: Vec<T>) -> Vec<Self::Handle> {
+        values.into_iter().map(Self::new_slot).collect()
+    }
+
+    fn new_slot(value: T) -> Self::Handle {
+        Arc::new(L::new(Slot::new(value)))
+    }
+
+    #[inline]
+    fn try_read(slot: &Self::Handle) -> Option<Self::ReadGuard> {
+        L::try_read(slot)
+    }
+
+    #[inline]
+    fn try_write(slot: &Self::Handle) -> Option<Self::WriteGuard> {
+        L::try_write(slot)
+    }
+
+    #[inline]
+    fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard {
+        L::downgrade(guard)
+    }
+}
+
+/// Storage of `AtomicRwLock` slots inside the slot table, whose guards can hold any owner of the lock unlike the guards of other locks.
+#[cfg(feature = "atomic_rw_lock")]
+mod contiguous {
+    use crate::atomic_rw_lock::{AtomicRwLock, AtomicRwLockReader, AtomicRwLockWriter, AtomicValue};
+    use crate::slot::Slot;
+    use crate::slot_storage::sealed::Storage;
+    use std::fmt::{Debug, Formatter};
+    use std::marker::PhantomData;
+    use std::ops::Deref;
+    use std::ptr::NonNull;
+
+    /// Storage keeping the slots in the cycler's slot table instead of an `Arc` per slot, with `L` around each slot.
+    /// The slots a cycler is built with are in one allocation and guards borrow them instead of counting references, so switching slots is a single atomic operation on the lock.
+    /// Used by `AtomicCycler`.
+    ///
+    /// ```
+    /// use cycler::atomic_rw_lock::AtomicRwLock;
+    /// use cycler::generic::{build_multiple_reader, GenericCyclerReader, GenericCyclerWriter};
+    /// use cycler::{Contiguous, Slot};
+    /// use std::sync::atomic::AtomicU8;
+    ///
+    /// type Storage = Contiguous<AtomicRwLock<Slot<u64>, AtomicU8>>;
+    /// let (writer, readers): (GenericCyclerWriter<u64, Storage>, Vec<GenericCyclerReader<u64, Storage>>) = build_multiple_reader(vec![0; 6]);
+    /// assert_eq!(readers.len(), 4);
+    /// # drop(writer);
+    /// ```
+    pub struct Contiguous<L>(PhantomData<fn() -> L>);
+    impl<L> Debug for Contiguous<L> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Contiguous")
+        }
+    }
+
+    /// A borrow of a slot in the slot table, held by the guards of a `Contiguous` cycler.
+    /// Its lifetime is erased, the generic cycler drops every guard before its `Arc` of the cycler owning the slot table.
+    pub struct SlotRef<L>(NonNull<L>);
+    impl<L> Deref for SlotRef<L> {
+        type Target = L;
+
+        #[inline]
+        fn deref(&self) -> &Self::Target {
+            // Safety: slots are never moved or freed while the cycler is alive, and the cycler outlives every guard holding this.
+            unsafe { self.0.as_ref() }
+        }
+    }
+    impl<L> Debug for SlotRef<L> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.debug_tuple("SlotRef").field(&self.0).finish()
+        }
+    }
+    // Safety: a `SlotRef` is a shared reference to the lock.
+    unsafe impl<L> Send for SlotRef<L> where L: Sync {}
+    unsafe impl<L> Sync for SlotRef<L> where L: Sync {}
+
+    impl<T, A> Storage<T> for Contiguous<AtomicRwLock<Slot<T>, A>> where A: AtomicValue {
+        type Handle = AtomicRwLock<Slot<T>, A>;
+        type ReadGuard = AtomicRwLockReader<Slot<T>, A, SlotRef<Self::Handle>>;
+        type WriteGuard = AtomicRwLockWriter<Slot<T>, A, SlotRef<Self::Handle>>;
+        const MAX_READERS: usize = A::MAX_READERS;
+
+        fn new_slots(values: Vec<T>) -> Vec<Self::Handle> {
+            values.into_iter().map(Self::new_slot).collect()
+        }
+
+        fn new_slot(value: T) -> Self::Handle {
+            AtomicRwLock::new(Slot::new(value))
+        }
+
+        #[inline]
+        fn try_read(slot: &Self::Handle) -> Option<Self::ReadGuard> {
+            AtomicRwLock::try_read_owned(SlotRef(NonNull::from(slot)))
+        }
+
+        #[inline]
+        fn try_write(slot: &Self::Handle) -> Option<Self::WriteGuard> {
+            AtomicRwLock::try_write_owned(SlotRef(NonNull::from(slot)))
+        }
+
+        #[inline]
+        fn downgrade(guard: Self::WriteGuard) -> Self::ReadGuard {
+            guard.downgrade()
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! The growable slot storage shared by the cycler backends.
+//! Slots are stored in segments that are never moved or freed until the cycler is dropped so locks and guards can keep referencing them while new slots are added.
+//! The slots a table is created with are its first segment, a plain slice indexed without a division or atomic loads as every publish and switch indexes it.
+
+use crate::error::ReaderLimitError;
+use crate::slot::Slot;
+use parking_lot::Mutex;
+use std::fmt::{Debug, Formatter};
+use std::ops::{DerefMut, Index};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// The most slots a cycler can have, its lock may allow fewer.
+pub(crate) const MAX_SLOTS: usize = u32::MAX as usize;
+
+/// A segment of slots, each slot is initialized once when added.
+type Segment<S> = Box<[OnceLock<S>]>;
+
+/// The registered readers and the slots the table shrank past.
+struct Readers<G> {
+    count: usize,
+    /// Write guards of the retired slots right after the last slot, the slot at `len` last.
+    retired: Vec<G>,
+}
+
+/// Table of slots that also tracks how many readers are registered.
+/// There are always at least `num_readers() + 2` slots so the writer can always find a free one.
+///
+/// When readers are dropped the slots added for them are retired from the end of the table, their data is dropped and the table keeps them write locked.
+/// A reader or writer that loaded an index before the table shrank can still index a retired slot but fails to lock it.
+/// A slot that is taken or the latest is retired by a later `shrink`, which the writer calls after publishing while `needs_shrink`.
+/// Retired slots are revived by the next readers added, the slots the table was created with are never retired.
+pub(crate) struct SlotTable<S, G> {
+    /// Declared before the segments so the guards are dropped while the slots they may borrow are still alive.
+    readers: Mutex<Readers<G>>,
+    /// The slots the table was created with.
+    first: Box<[S]>,
+    /// Segment `n` of the ones added holds `first.len() << (n + 1)` slots, only allocated once a slot is added to it.
+    grown: Box<[OnceLock<Segment<S>>]>,
+    /// The amount of slots in use, slots are added and retired in order.
+    len: AtomicUsize,
+    max_len: usize,
+    /// Set while there are more slots than needed that couldn't be retired yet.
+    shrinking: AtomicBool,
+}
+impl<S, G> SlotTable<S, G> {
+    /// Creates a table holding `slots` and `readers` readers that can grow up to `max_len` slots.
+    /// There must be at least `readers + 2` slots.
+    pub(crate) fn new(slots: Vec<S>, readers: usize, max_len: usize) -> Self {
+        debug_assert!(readers + 2 <= slots.len());
+        let first = slots.len();
+        let mut capacity = first;
+        let mut grown = Vec::new();
+        while capacity < max_len {
+            capacity = capacity.saturating_add(first << (grown.len() + 1));
+            grown.push(OnceLock::new());
+        }
+        Self {
+            readers: Mutex::new(Readers { count: readers, retired: Vec::new() }),
+            first: slots.into_boxed_slice(),
+            grown: grown.into_boxed_slice(),
+            len: AtomicUsize::new(first),
+            max_len,
+            shrinking: AtomicBool::new(false),
+        }
+    }
+
+    /// The amount of slots.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// The amount of registered readers.
+    pub(crate) fn num_readers(&self) -> usize {
+        self.readers.lock().count
+    }
+
+    /// The amount of retired slots.
+    #[cfg(test)]
+    pub(crate) fn num_retired(&self) -> usize {
+        self.readers.lock().retired.len()
+    }
+
+    /// Registers a new reader, reviving a retired slot or adding the slot made by `new_slot` from `new_value` if there is no spare slot.
+    pub(crate) fn add_reader<T>(&self, new_value: impl FnOnce() -> T, new_slot: impl FnOnce(T) -> S) -> Result<(), ReaderLimitError>
+    where
+        G: DerefMut<Target = Slot<T>>,
+    {
+        let mut readers = self.readers.lock();
+        let len = self.len();
+        if len < readers.count + 3 {
+            match readers.retired.pop() {
+                Some(mut guard) => {
+                    guard.revive(new_value());
+                    drop(guard);
+                    self.len.store(len + 1, Ordering::Release);
+                }
+                None if len >= self.max_len => return Err(ReaderLimitError::new(self.max_len)),
+                None => self.push(new_slot(new_value())),
+            }
+        }
+        readers.count += 1;
+        Ok(())
+    }
+
+    /// Unregisters a reader, then retires the slots no longer needed with `try_retire` as in `shrink`.
+    /// The reader must have released its slot before calling this.
+    pub(crate) fn remove_reader<T>(&self, try_retire: impl Fn(&S, usize) -> Option<G>)
+    where
+        G: DerefMut<Target = Slot<T>>,
+    {
+        let mut readers = self.readers.lock();
+        readers.count -= 1;
+        self.retire(&mut readers, try_retire);
+    }
+
+    /// Returns true if there are slots left to retire.
+    #[inline]
+    pub(crate) fn needs_shrink(&self) -> bool {
+        self.shrinking.load(Ordering::Relaxed)
+    }
+
+    /// Retires slots from the end of the table while there are more than needed, unless readers are being added or removed.
+    /// `try_retire` is given the last slot and its index, it returns the slot's write guard if the slot is free and no reader can lock it once it is left locked.
+    /// A slot that can't be retired stops the shrinking until the next call.
+    pub(crate) fn shrink<T>(&self, try_retire: impl Fn(&S, usize) -> Option<G>)
+    where
+        G: DerefMut<Target = Slot<T>>,
+    {
+        if let Some(mut readers) = self.readers.try_lock() {
+            self.retire(&mut readers, try_retire);
+        }
+    }
+
+    fn retire<T>(&self, readers: &mut Readers<G>, try_retire: impl Fn(&S, usize) -> Option<G>)
+    where
+        G: DerefMut<Target = Slot<T>>,
+    {
+        let needed = (readers.count + 2).max(self.first.len());
+        let mut len = self.len();
+        while len > needed {
+            let mut guard = match try_retire(&self[len - 1], len - 1) {
+                Some(guard) => guard,
+                None => break,
+            };
+            guard.retire();
+            readers.retired.push(guard);
+            len -= 1;
+            self.len.store(len, Ordering::Release);
+        }
+        self.shrinking.store(len > needed, Ordering::Relaxed);
+    }
+
+    /// Finds the added segment and the offset within it of `index`, which must be past the first segment.
+    #[inline]
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let first = self.first.len();
+        let segment = (index / first + 1).ilog2() as usize;
+        (segment - 1, index - first * ((1 << segment) - 1))
+    }
+
+    /// Must be called with `readers` locked and no retired slots.
+    fn push(&self, slot: S) {
+        let index = self.len.load(Ordering::Relaxed);
+        let (segment, offset) = self.locate(index);
+        let segment = self.grown[segment]
+            .get_or_init(|| (0..self.first.len() << (segment + 1)).map(|_| OnceLock::new()).collect());
+        if segment[offset].set(slot).is_err() {
+            unreachable!("Slot {} initialized twice", index);
+        }
+        self.len.store(index + 1, Ordering::Release);
+    }
+}
+impl<S, G> Index<usize> for SlotTable<S, G> {
+    type Output = S;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        if let Some(slot) = self.first.get(index) {
+            return slot;
+        }
+        let (segment, offset) = self.locate(index);
+        self.grown[segment]
+            .get()
+            .and_then(|segment| segment[offset].get())
+            .unwrap_or_else(|| panic!("Slot {} out of bounds, only {} slots", index, self.len()))
+    }
+}
+impl<S, G> Debug for SlotTable<S, G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let readers = self.readers.lock();
+        f.debug_struct("SlotTable")
+            .field("len", &self.len())
+            .field("max_len", &self.max_len)
+            .field("readers", &readers.count)
+            .field("retired", &readers.retired.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slot::Slot;
+    use crate::slot_table::SlotTable;
+    use parking_lot::lock_api::ArcRwLockWriteGuard;
+    use parking_lot::{RawRwLock, RwLock};
+    use std::sync::Arc;
+
+    type Lock = Arc<RwLock<Slot<usize>>>;
+    type Table = SlotTable<Lock, ArcRwLockWriteGuard<RawRwLock, Slot<usize>>>;
+
+    fn new_slot(value: usize) -> Lock {
+        Arc::new(RwLock::new(Slot::new(value)))
+    }
+
+    fn value(table: &Table, index: usize) -> usize {
+        *table[index].read().data()
+    }
+
+    #[test]
+    fn grow_test() {
+        let table: Table = SlotTable::new((0..3).map(new_slot).collect(), 1, 20);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.num_readers(), 1);
+        for index in 3..20 {
+            table.add_reader(|| index, new_slot).unwrap();
+        }
+        assert_eq!(table.len(), 20);
+        assert_eq!(table.num_readers(), 18);
+        assert!((0..20).all(|index| value(&table, index) == index));
+        assert_eq!(table.add_reader(|| 20, new_slot).unwrap_err().max_slots(), 20);
+    }
+
+    #[test]
+    fn shrink_test() {
+        let table: Table = SlotTable::new((0..3).map(new_slot).collect(), 1, 20);
+        for index in 3..10 {
+            table.add_reader(|| index, new_slot).unwrap();
+        }
+        assert_eq!(table.len(), 10);
+
+        // The slot at 7 is still locked by its reader so the table stops shrinking there.
+        let held = table[7].read();
+        for _ in 0..4 {
+            table.remove_reader(|slot, _| slot.try_write_arc());
+        }
+        assert_eq!((table.len(), table.num_retired()), (8, 2));
+        drop(held);
+        table.remove_reader(|slot, _| slot.try_write_arc());
+        assert_eq!((table.len(), table.num_retired()), (5, 5));
+        assert!(table[8].try_read().is_none());
+
+        // The slots the table was created with are kept.
+        for _ in 0..3 {
+            table.remove_reader(|slot, _| slot.try_write_arc());
+        }
+        assert_eq!((table.len(), table.num_readers(), table.num_retired()), (3, 0, 7));
+
+        // Retired slots are revived in order before new slots are added, the first reader added uses a spare slot.
+        for index in 0..8 {
+            table.add_reader(|| 100 + index, |_| unreachable!("Only retired slots should be revived")).unwrap();
+        }
+        assert_eq!((table.len(), table.num_readers(), table.num_retired()), (10, 8, 0));
+        assert!((3..10).all(|index| value(&table, index) == 98 + index));
+        assert_eq!(table[3].read().version, 0);
+        table.add_reader(|| 200, new_slot).unwrap();
+        assert_eq!(value(&table, 10), 200);
+    }
+}
@@ -0,0 +1,59 @@
+//! The atomics and cells the lock-free parts of the cycler are built on.
+//! Building with `RUSTFLAGS="--cfg loom"` swaps them for loom's so the `loom` tests can check every interleaving of the writer and readers.
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::AtomicUsize;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::AtomicUsize;
+
+#[cfg(all(any(feature = "atomic_rw_lock", feature = "seq_lock"), not(loom)))]
+pub(crate) use std::sync::atomic::{fence, AtomicU64};
+#[cfg(all(any(feature = "atomic_rw_lock", feature = "seq_lock"), loom))]
+pub(crate) use loom::sync::atomic::{fence, AtomicU64};
+#[cfg(all(feature = "atomic_rw_lock", not(loom)))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU8};
+#[cfg(all(feature = "atomic_rw_lock", loom))]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU8};
+
+#[cfg(all(any(feature = "atomic_rw_lock", feature = "triple_buffer"), loom))]
+pub(crate) use loom::cell::UnsafeCell;
+
+/// `std::cell::UnsafeCell` with the closure based access of loom's, which loom uses to check accesses don't race.
+#[cfg(all(any(feature = "atomic_rw_lock", feature = "triple_buffer"), not(loom)))]
+#[derive(Debug)]
+pub(crate) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+#[cfg(all(any(feature = "atomic_rw_lock", feature = "triple_buffer"), not(loom)))]
+impl<T> UnsafeCell<T> {
+    #[inline]
+    pub(crate) fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    #[cfg(feature = "atomic_rw_lock")]
+    #[inline]
+    pub(crate) fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+
+    /// Calls `f` with a pointer for shared access to the value.
+    #[inline]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    /// Calls `f` with a pointer for exclusive access to the value.
+    #[inline]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+/// Called between attempts of a retry loop that waits on another thread.
+/// Under loom this yields so the model runs the other threads instead of exploring the loop forever.
+#[inline]
+pub(crate) fn spin_loop() {
+    #[cfg(not(loom))]
+    std::hint::spin_loop();
+    #[cfg(loom)]
+    loom::thread::yield_now();
+}
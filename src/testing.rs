@@ -0,0 +1,577 @@
+//! A test suite every cycler should pass, used by the built-in backends and usable by other backends.
+//! Activated by the `testing` feature.
+//!
+//! Each test takes a function building a cycler from its initial values, the first value being the one readers start on and the second the one the writer starts on.
+//! `run_suite` runs every test, `run_backend_suite` also checks adding and cloning readers and replaying changes for a `CyclerBackend`.
+//!
+//! ```no_run
+//! // In a test of the backend.
+//! cycler::testing::run_suite(cycler::rw_lock_cycler::build_multiple_reader);
+//! cycler::testing::run_backend_suite::<cycler::RwLockBackend>();
+//! ```
+
+use crate::builder::{BackendReader, BackendWriter, CyclerBackend};
+use crate::change_log::ChangeLog;
+use crate::generic;
+use crate::slot::CloneContext;
+use crate::traits::*;
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+/// The data the suite stores in the cycler.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TestData {
+    /// Set to the version being published by most tests.
+    pub test1: usize,
+    /// Heap allocated data.
+    pub test2: String,
+    /// More heap allocated data.
+    pub test3: Box<usize>,
+}
+impl Default for TestData {
+    fn default() -> Self {
+        Self { test1: 0, test2: "Start".to_string(), test3: Box::new(100) }
+    }
+}
+impl ReadAccess for TestData {
+    type Read = Self;
+
+    fn read_data(&self) -> &Self::Read {
+        self
+    }
+}
+impl WriteAccess for TestData {
+    type Write = Self;
+
+    fn write_data(&self) -> &Self::Write {
+        self
+    }
+
+    fn write_data_mut(&mut self) -> &mut Self::Write {
+        self
+    }
+}
+
+/// Data that records changes for replaying, counting how many times it was fully cloned.
+#[derive(Debug, Default)]
+pub struct ReplayData {
+    /// The values changes are applied to.
+    pub values: Vec<usize>,
+    /// The changes of the latest versions, each setting `values[change.0]` to `change.1`.
+    pub log: ChangeLog<(usize, usize)>,
+    /// How many clones this block is from the initial values.
+    pub full_clones: usize,
+}
+impl ReplayData {
+    /// Creates 4 zeroed values with a change log remembering `capacity` changes.
+    pub fn new(capacity: usize) -> Self {
+        Self { values: vec![0; 4], log: ChangeLog::new(capacity), full_clones: 0 }
+    }
+}
+impl Clone for ReplayData {
+    fn clone(&self) -> Self {
+        Self { values: self.values.clone(), log: self.log.clone(), full_clones: self.full_clones + 1 }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.values.clone_from(&source.values);
+        self.log.clone_from(&source.log);
+        self.full_clones = source.full_clones + 1;
+    }
+}
+impl ReplayChanges for ReplayData {
+    type Change = (usize, usize);
+
+    fn apply_change(&mut self, change: &Self::Change) {
+        self.values[change.0] = change.1;
+    }
+
+    fn change_log(&self) -> &ChangeLog<Self::Change> {
+        &self.log
+    }
+
+    fn change_log_mut(&mut self) -> &mut ChangeLog<Self::Change> {
+        &mut self.log
+    }
+}
+impl ReadAccess for ReplayData {
+    type Read = Self;
+
+    fn read_data(&self) -> &Self::Read {
+        self
+    }
+}
+impl WriteAccess for ReplayData {
+    type Write = Self;
+
+    fn write_data(&self) -> &Self::Write {
+        self
+    }
+
+    fn write_data_mut(&mut self) -> &mut Self::Write {
+        self
+    }
+}
+
+/// Runs every test of the suite against the cycler built by `build`.
+pub fn run_suite<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData> + Send + 'static,
+    R: UniversalCyclerReader<TestData> + Send + 'static,
+{
+    single_reader_test(&build);
+    multiple_reader_test(&build);
+    version_test(&build);
+    clone_fn_test(&build);
+    mut_fn_test(&build);
+    context_test(&build);
+    async_test(&build);
+    wait_test(&build);
+    drop_test(&build);
+    stress_test(&build);
+}
+
+/// Runs `run_suite` against the cycler of backend `B`, then checks adding and cloning readers and replaying changes.
+pub fn run_backend_suite<B>()
+where
+    B: CyclerBackend,
+    BackendWriter<TestData, B>: Send + 'static,
+    BackendReader<TestData, B>: Send + 'static,
+{
+    run_suite(generic::build_multiple_reader::<TestData, B::Storage<TestData>>);
+    replay_test(generic::build_multiple_reader::<ReplayData, B::Storage<ReplayData>>);
+    add_reader_test::<B>();
+    try_clone_test::<B>();
+}
+
+/// Checks a single reader cycler through every universal trait.
+pub fn single_reader_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    let (writer, mut readers) = build(vec![TestData::default(); 3]);
+    assert_eq!(readers.len(), 1);
+    check_universal(writer, readers.pop().unwrap());
+}
+
+/// Checks every reader of a multi reader cycler moves independently and the writer never waits on them.
+pub fn multiple_reader_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    let (mut writer, mut readers) = build(vec![TestData::default(); 5]);
+    assert_eq!(readers.len(), 3);
+    for version in 1..20 {
+        writer.write_data_mut().test1 = version;
+        assert_eq!(writer.write_next(), version as u64);
+        // Reader `n` only reads every `n + 1` versions, holding on to older slots in between.
+        for (index, reader) in readers.iter_mut().enumerate() {
+            if version % (index + 1) == 0 {
+                assert!(reader.read_latest());
+                assert_eq!(reader.version(), version as u64);
+                assert!(!reader.read_latest());
+            }
+            assert_eq!(reader.read_data().test1 as u64, reader.version());
+        }
+    }
+}
+
+/// Checks every publish gets the next version and `read_latest` returns whether the version changed.
+pub fn version_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    let (mut writer, mut readers) = build(vec![TestData::default(); 3]);
+    let mut reader = readers.pop().unwrap();
+    assert_eq!(reader.version(), 0);
+    assert!(!reader.read_latest());
+    assert_eq!(writer.write_next(), 1);
+    assert_eq!(writer.write_next_fn(TestData::clone_from), 2);
+    assert!(reader.read_latest());
+    assert_eq!(reader.version(), 2);
+    assert!(!reader.read_latest());
+    assert_eq!(writer.write_next_mut_fn(|to, from| to.clone_from(from)), 3);
+    assert!(reader.read_latest());
+    assert_eq!(reader.version(), 3);
+}
+
+/// Checks every `CyclerWriterFn` and `CyclerWriterContextFn` function publishes and clones into the next block.
+pub fn clone_fn_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    fn clone_fn(to: &mut TestData, from: &TestData) {
+        to.clone_from(from);
+        to.test2.push('!');
+    }
+
+    let (mut writer, mut readers) = build(vec![TestData::default(); 3]);
+    let mut reader = readers.pop().unwrap();
+    let mut contexts = Vec::new();
+    let mut expected = TestData::default();
+    for version in 1..=8u64 {
+        writer.write_data_mut().test1 = version as usize;
+        expected.test1 = version as usize;
+        let published = match version {
+            1 => writer.write_next_fn(clone_fn),
+            2 => writer.write_next_fn_impl(clone_fn),
+            3 => writer.write_next_fn_dyn(&mut clone_fn),
+            4 => writer.write_next_fn_dyn_boxed(Box::new(clone_fn)),
+            5 => writer.write_next_context_fn(|to, from, _| clone_fn(to, from)),
+            6 => writer.write_next_context_fn_impl(|to, from, context| {
+                contexts.push(context);
+                clone_fn(to, from)
+            }),
+            7 => writer.write_next_context_fn_dyn(&mut |to, from, context| {
+                contexts.push(context);
+                clone_fn(to, from)
+            }),
+            _ => writer.write_next_context_fn_dyn_boxed(Box::new(|to, from, _| clone_fn(to, from))),
+        };
+        assert_eq!(published, version);
+        assert!(reader.read_latest());
+        assert_eq!(*reader.read_data(), expected);
+        expected.test2.push('!');
+        assert_eq!(*writer.write_data(), expected);
+    }
+    assert_eq!(contexts.len(), 2);
+    for (context, version) in contexts.into_iter().zip(6..) {
+        assert_eq!(context.source_version, version);
+        assert!(context.target_version < version);
+    }
+}
+
+/// Checks every `CyclerWriterMutFn` function publishes the block as changed by the clone function.
+pub fn mut_fn_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    /// Marks the block being published so readers can tell it went through the function.
+    fn clone_fn(to: &mut TestData, from: &mut TestData) {
+        *from.test3 += 1;
+        to.clone_from(from);
+    }
+
+    let (mut writer, mut readers) = build(vec![TestData::default(); 3]);
+    let mut reader = readers.pop().unwrap();
+    for version in 1..=4u64 {
+        writer.write_data_mut().test1 = version as usize;
+        let published = match version {
+            1 => writer.write_next_mut_fn(clone_fn),
+            2 => writer.write_next_mut_fn_impl(clone_fn),
+            3 => writer.write_next_mut_fn_dyn(&mut clone_fn),
+            _ => writer.write_next_mut_fn_dyn_boxed(Box::new(clone_fn)),
+        };
+        assert_eq!(published, version);
+        assert!(reader.read_latest());
+        assert_eq!(reader.read_data().test1 as u64, version);
+        assert_eq!(*reader.read_data().test3 as u64, 100 + version);
+        assert_eq!(*writer.write_data(), *reader.read_data());
+    }
+}
+
+/// Checks the `CloneContext` given to clone functions while the reader stays on the initial block.
+/// The writer alternates between the two other blocks, each time cloning into the block published before the last.
+pub fn context_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    let (mut writer, _readers) = build(vec![TestData::default(); 3]);
+    let mut contexts = Vec::new();
+    for version in 1..=4 {
+        assert_eq!(writer.write_next_context_fn_dyn(&mut |to, from, context| {
+            to.clone_from(from);
+            contexts.push(context);
+        }), version);
+    }
+    let versions: Vec<_> = contexts.iter().map(|context| (context.target_version, context.source_version)).collect();
+    assert_eq!(versions, [(0, 1), (1, 2), (2, 3), (3, 4)]);
+    let slots = |context: &CloneContext| context.slot;
+    assert_ne!(slots(&contexts[0]), slots(&contexts[1]));
+    assert_eq!(slots(&contexts[0]), slots(&contexts[2]));
+    assert_eq!(slots(&contexts[1]), slots(&contexts[3]));
+}
+
+/// Checks a reader waiting with `read_latest_async` on another thread is woken by the next publish.
+pub fn async_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData> + Send + 'static,
+{
+    let (mut writer, mut readers) = build(vec![TestData::default(); 3]);
+    let mut reader = readers.pop().unwrap();
+    assert!(reader.poll_read_latest(&mut Context::from_waker(Waker::noop())).is_pending());
+    let handle = thread::spawn(move || {
+        block_on(reader.read_latest_async());
+        assert_eq!(reader.version(), 1);
+        assert_eq!(reader.read_data().test1, 100);
+    });
+    writer.write_data_mut().test1 = 100;
+    writer.write_next();
+    handle.join().unwrap();
+}
+
+/// Checks a reader thread waiting with `wait_for_update` is woken by the next publish and times out without one.
+pub fn wait_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData> + Send + 'static,
+{
+    let (mut writer, mut readers) = build(vec![TestData::default(); 3]);
+    let mut reader = readers.pop().unwrap();
+    assert!(!reader.wait_for_update(Duration::from_millis(10)));
+    let handle = thread::spawn(move || {
+        assert!(reader.wait_for_update(Duration::from_secs(60)));
+        reader.read_latest();
+        assert_eq!(reader.read_data().test1, 100);
+        assert!(!reader.wait_for_update(Duration::from_millis(10)));
+    });
+    writer.write_data_mut().test1 = 100;
+    writer.write_next();
+    handle.join().unwrap();
+}
+
+/// Checks a single reader cycler replays changes into recycled blocks and clones blocks that are too far behind.
+/// The change logs of the built blocks must have a capacity of 2.
+pub fn replay_test<W, R>(build: impl Fn(Vec<ReplayData>) -> (W, Vec<R>))
+where
+    W: CyclerWriterReplay<ReplayData>,
+    R: CyclerReader<ReplayData>,
+{
+    let (mut writer, mut readers) = build((0..3).map(|_| ReplayData::new(2)).collect());
+    let mut reader = readers.pop().unwrap();
+    // Slot 2 has never been published so is cloned.
+    writer.write_change((0, 5));
+    assert_eq!(writer.write_next_replay(), 1);
+    assert_eq!(writer.write_data().full_clones, 1);
+    // The reader holds slot 0 so slot 1 is recycled, it only missed the version 2 change.
+    writer.write_change((1, 7));
+    assert_eq!(writer.write_next_replay(), 2);
+    assert_eq!(writer.write_data().values, [5, 7, 0, 0]);
+    assert_eq!(writer.write_data().full_clones, 0);
+    assert!(reader.read_latest());
+    assert_eq!(reader.read_data().values, [5, 7, 0, 0]);
+    // Slot 0 has never been published by the writer so is cloned.
+    writer.write_change((2, 9));
+    assert_eq!(writer.write_next_replay(), 3);
+    assert_eq!(writer.write_data().values, [5, 7, 9, 0]);
+    assert_eq!(writer.write_data().full_clones, 1);
+    assert!(reader.read_latest());
+    // Slot 2 missed versions 3 and 4 but the log only remembers the 2 changes of version 4.
+    writer.write_change((3, 1));
+    writer.write_change((3, 2));
+    assert_eq!(writer.write_next_replay(), 4);
+    assert_eq!(writer.write_data().values, [5, 7, 9, 2]);
+    assert_eq!(writer.write_data().full_clones, 2);
+    assert!(reader.read_latest());
+    assert_eq!(reader.read_data().values, [5, 7, 9, 2]);
+}
+
+/// Checks readers added by the writer of backend `B` read the latest block, reuse the slots of dropped readers and can outnumber the slots a `u8` indexes.
+pub fn add_reader_test<B>()
+where
+    B: CyclerBackend,
+    BackendWriter<TestData, B>: Send + 'static,
+    BackendReader<TestData, B>: Send + 'static,
+{
+    let (mut writer, mut readers) = generic::build_multiple_reader::<TestData, B::Storage<TestData>>(vec![TestData::default(); 3]);
+    writer.write_data_mut().test1 = 1;
+    writer.write_next();
+    let mut added = writer.add_reader().unwrap();
+    assert_eq!(writer.cycler.num_readers(), 2);
+    assert_eq!(writer.cycler.data_slots.len(), 4);
+    assert_eq!(added.read_data().test1, 1);
+    writer.write_data_mut().test1 = 2;
+    writer.write_next();
+    assert!(added.read_latest());
+    assert_eq!(added.read_data().test1, 2);
+
+    // The slot of a dropped reader is reused.
+    drop(readers.pop());
+    assert_eq!(writer.cycler.num_readers(), 1);
+    let mut readers = vec![added, writer.add_reader().unwrap()];
+    assert_eq!(writer.cycler.data_slots.len(), 4);
+    // More readers than slots can be indexed by a `u8`.
+    while writer.cycler.data_slots.len() < 300 {
+        readers.push(writer.add_reader().unwrap());
+    }
+    for reader in &mut readers {
+        reader.read_latest();
+        assert_eq!(reader.read_data().test1, 2);
+    }
+    monotonic_stress_test(writer, readers, if cfg!(miri) { 10 } else { 1_000 });
+}
+
+/// Checks readers cloned from a reader of backend `B` read the latest block, reuse the slots of dropped readers and can outnumber the slots a `u8` indexes.
+pub fn try_clone_test<B>()
+where
+    B: CyclerBackend,
+    BackendWriter<TestData, B>: Send + 'static,
+    BackendReader<TestData, B>: Send + 'static,
+{
+    let (mut writer, mut readers) = generic::build_multiple_reader::<TestData, B::Storage<TestData>>(vec![TestData::default(); 3]);
+    let mut reader = readers.pop().unwrap();
+    writer.write_data_mut().test1 = 1;
+    writer.write_next();
+    let cloned = reader.try_clone().unwrap();
+    assert_eq!(writer.cycler.num_readers(), 2);
+    assert_eq!(writer.cycler.data_slots.len(), 4);
+    assert_eq!(cloned.version(), 1);
+    assert_eq!(cloned.read_data().test1, 1);
+    assert!(reader.read_latest());
+
+    let mut readers = vec![reader, cloned];
+    while writer.cycler.data_slots.len() < 300 {
+        let cloned = readers[0].try_clone().unwrap();
+        readers.push(cloned);
+    }
+
+    // A dropped reader's slot is reused without growing.
+    readers.pop();
+    readers.push(readers[0].try_clone().unwrap());
+    assert_eq!(writer.cycler.data_slots.len(), 300);
+    monotonic_stress_test(writer, readers, if cfg!(miri) { 10 } else { 1_000 });
+}
+
+/// Checks the writer and readers keep working when the others are dropped, in any order.
+pub fn drop_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    // Readers outlive the writer.
+    let (mut writer, mut readers) = build(vec![TestData::default(); 4]);
+    writer.write_data_mut().test1 = 1;
+    writer.write_next();
+    drop(writer);
+    for reader in &mut readers {
+        assert!(reader.read_latest());
+        assert_eq!(reader.read_data().test1, 1);
+        assert!(!reader.read_latest());
+        assert!(!reader.wait_for_update(Duration::ZERO));
+    }
+    // Readers dropped in a different order than they were built.
+    drop(readers.pop());
+    drop(readers.remove(0));
+    drop(readers);
+
+    // The writer outlives the readers.
+    let (mut writer, readers) = build(vec![TestData::default(); 4]);
+    drop(readers);
+    for version in 1..10 {
+        writer.write_data_mut().test1 = version;
+        assert_eq!(writer.write_next(), version as u64);
+        assert_eq!(writer.write_data().test1, version);
+    }
+}
+
+/// Publishes 1000 blocks while 4 readers on other threads check versions never go backwards and each block is the one published as its version.
+pub fn stress_test<W, R>(build: impl Fn(Vec<TestData>) -> (W, Vec<R>))
+where
+    W: UniversalCyclerWriter<TestData> + Send + 'static,
+    R: UniversalCyclerReader<TestData> + Send + 'static,
+{
+    let (writer, readers) = build(vec![TestData::default(); 6]);
+    monotonic_stress_test(writer, readers, if cfg!(miri) { 10 } else { 1_000 });
+}
+
+/// Minimal executor that parks the current thread until the future is woken.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: Future,
+{
+    struct ThreadWaker(Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Publishes `publishes` blocks while every reader spins on `read_latest`.
+/// Asserts that no reader's version ever decreases and that every block read is the one published as its version.
+/// Publishing continues from the latest version the readers hold, which must be the latest published.
+pub fn monotonic_stress_test<W, R>(mut writer: W, readers: Vec<R>, publishes: u64)
+where
+    W: CyclerWriterDefault<TestData> + Send + 'static,
+    R: CyclerReader<TestData> + Send + 'static,
+{
+    let first = readers.iter().map(|reader| reader.version()).max().unwrap_or(0) + 1;
+    let publishes = first + publishes - 1;
+    let handles: Vec<_> = readers
+        .into_iter()
+        .map(|mut reader| {
+            thread::spawn(move || {
+                let mut last_version = reader.version();
+                while last_version < publishes {
+                    let changed = reader.read_latest();
+                    let version = reader.version();
+                    assert!(version >= last_version, "Reader went from version {} to {}", last_version, version);
+                    assert_eq!(changed, version != last_version);
+                    assert_eq!(reader.read_data().test1 as u64, version);
+                    last_version = version;
+                }
+            })
+        })
+        .collect();
+    for version in first..=publishes {
+        writer.write_data_mut().test1 = version as usize;
+        assert_eq!(writer.write_next(), version);
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+/// Checks a single reader cycler works through the universal traits, both generically and as trait objects.
+/// The reader must be reading version 0 and the writer writing a copy of it.
+pub fn check_universal<W, R>(mut writer: W, mut reader: R)
+where
+    W: UniversalCyclerWriter<TestData>,
+    R: UniversalCyclerReader<TestData>,
+{
+    {
+        let writer: &mut dyn UniversalCyclerWriter<TestData> = &mut writer;
+        let reader: &mut dyn UniversalCyclerReader<TestData> = &mut reader;
+        assert_eq!(reader.version(), 0);
+        assert!(!reader.wait_for_update(Duration::ZERO));
+        writer.write_data_mut().test1 = 1;
+        assert_eq!(writer.write_next(), 1);
+        assert!(reader.wait_for_update(Duration::ZERO));
+        assert!(reader.read_latest());
+        assert_eq!(reader.read_data().test1, 1);
+        writer.write_data_mut().test1 = 2;
+        assert_eq!(writer.write_next_fn(TestData::clone_from), 2);
+        writer.write_data_mut().test1 = 3;
+        assert_eq!(writer.write_next_mut_fn_dyn(&mut |to, from| to.clone_from(from)), 3);
+        writer.write_data_mut().test1 = 4;
+        assert_eq!(writer.write_next_context_fn_dyn(&mut |to, from, _| to.clone_from(from)), 4);
+        assert!(reader.read_latest());
+        assert_eq!(reader.read_data().test1, 4);
+    }
+
+    writer.write_data_mut().test1 = 5;
+    assert_eq!(writer.write_next_fn_impl(|to, from| to.clone_from(from)), 5);
+    block_on(reader.read_latest_async());
+    assert_eq!(reader.version(), 5);
+    assert_eq!(reader.read_data().test1, 5);
+}
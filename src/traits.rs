@@ -3,6 +3,12 @@
 //! while `CyclerReader` is a single trait that allows the reader to move to the latest block.
 //! `WriteAccess` and `ReadAccess` are traits that should be implemented for any type that goes into a cycler.
 
+use crate::async_read::ReadLatestFuture;
+use crate::change_log::ChangeLog;
+use crate::slot::CloneContext;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
 /// Trait that can be implemented to ensure a type is send
 pub trait EnsureSend: Send {}
 /// Trait that can be implemented to ensure a type is sync
@@ -38,6 +44,21 @@ pub trait ReadAccess {
 /// Ensure `ReadAccess` can be trait object
 impl<R> dyn ReadAccess<Read = R> {}
 
+/// This trait can be implemented for data that records its changes in a `ChangeLog`.
+/// This allows `CyclerWriterReplay` to bring a recycled block up to date by replaying only the changes it missed instead of cloning the whole block.
+/// The change log is usually kept in data only the writer can access.
+pub trait ReplayChanges: Clone {
+    /// A single change to the data.
+    type Change: Clone;
+
+    /// Applies `change` to this data, this should not record the change.
+    fn apply_change(&mut self, change: &Self::Change);
+    /// Gets shared access to the change log contained
+    fn change_log(&self) -> &ChangeLog<Self::Change>;
+    /// Gets exclusive access to the change log contained
+    fn change_log_mut(&mut self) -> &mut ChangeLog<Self::Change>;
+}
+
 /// This trait is implemented for the write half of a cycler.
 pub trait CyclerWriter<T>: WriteAccess<Write = T::Write>
 where
@@ -54,13 +75,15 @@ where
     T: WriteAccess,
 {
     /// Moves the writer to the next block cloning the previously written block using a default function.
-    fn write_next(&mut self);
+    /// Returns the version the previously written block was published as.
+    fn write_next(&mut self) -> u64;
 }
 /// Ensure `CyclerWriterDefault` can be trait object
 impl<T> dyn CyclerWriterDefault<T> where T: WriteAccess {}
 
 /// This trait enables the write half of the cycler to move to the next block using a given clone function.
 /// This function follows the signature of `Clone::clone_from`, meaning the arguments are (to, from).
+/// Every function returns the version the previously written block was published as.
 pub trait CyclerWriterFn<T>: CyclerWriter<T>
 where
     T: WriteAccess,
@@ -68,23 +91,24 @@ where
     /// Moves the writer to the next block cloning using an `fn` pointer.
     /// Arguments are (to, from)
     /// This function uses an `fn` pointer so has no additional runtime cost and can be called on a trait object.
-    fn write_next_fn(&mut self, clone_fn: fn(&mut T, &T));
+    fn write_next_fn(&mut self, clone_fn: fn(&mut T, &T)) -> u64;
     /// Moves the writer to the next block cloning using an `FnOnce` impl
     /// Arguments are (to, from)
     /// This function is generic over the function reducing runtime cost but cannot be called on trait objects.
-    fn write_next_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T))
+    fn write_next_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T)) -> u64
     where
         Self: Sized;
     /// Moves the writer to the next block cloning using an `FnMut` dynamic reference.
     /// Arguments are (to, from)
     /// This function takes a dyn pointer so a v-table lookup is necessary.
     /// `write_next_fn` is preferred if the function can be an `fn` pointer or `write_next_fn_impl` is preferred if self is sized.
-    fn write_next_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T));
+    fn write_next_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T)) -> u64;
     /// Moves the writer to the next block cloning using a boxed `FnOnce`
     /// Arguments are (to, from)
     /// This function takes a boxed dyn pointer so a v-table lookup is necessary.
     /// `write_next_fn_dyn` is preferred if the function can be coerced into an `FnMut` as no heap allocation will be necessary.
-    fn write_next_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T)>);
+    #[allow(clippy::type_complexity)]
+    fn write_next_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T)>) -> u64;
 }
 /// Ensure `CyclerWriterFn` can be trait object
 impl<T> dyn CyclerWriterFn<T> where T: WriteAccess {}
@@ -92,6 +116,7 @@ impl<T> dyn CyclerWriterFn<T> where T: WriteAccess {}
 /// This trait enables the write half of the cycler to move to the next block using a clone function that takes a mutable reference to the previous block.
 /// This is not preferable as optimizations where the reader can read the previous block while it's being cloned are not possible.
 /// The function arguments are (from, to)
+/// Every function returns the version the previously written block was published as.
 pub trait CyclerWriterMutFn<T>: CyclerWriter<T>
 where
     T: WriteAccess,
@@ -99,58 +124,168 @@ where
     /// Moves the writer to the next block cloning using an `fn` pointer.
     /// Arguments are (to, from)
     /// This function uses an `fn` pointer so has no additional runtime cost and can be called on a trait object.
-    fn write_next_mut_fn(&mut self, clone_fn: fn(&mut T, &mut T));
+    fn write_next_mut_fn(&mut self, clone_fn: fn(&mut T, &mut T)) -> u64;
     /// Moves the writer to the next block cloning using an `FnOnce` impl
     /// Arguments are (to, from)
     /// This function is generic over the function reducing runtime cost but cannot be called on trait objects.
-    fn write_next_mut_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &mut T))
+    fn write_next_mut_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64
     where
         Self: Sized;
     /// Moves the writer to the next block cloning using an `FnMut` dynamic reference.
     /// Arguments are (to, from)
     /// This function takes a dyn pointer so a v-table lookup is necessary.
     /// `write_next_mut_fn` is preferred if the function can be an `fn` pointer or `write_next_mut_fn_impl` is preferred if self is sized.
-    fn write_next_mut_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &mut T));
+    fn write_next_mut_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &mut T)) -> u64;
     /// Moves the writer to the next block cloning using a boxed `FnOnce`
     /// Arguments are (to, from)
     /// This function takes a boxed dyn pointer so a v-table lookup is necessary.
     /// `write_next_mut_fn_dyn` is preferred if the function can be coerced into an `FnMut` as no heap allocation will be necessary.
-    fn write_next_mut_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &mut T)>);
+    #[allow(clippy::type_complexity)]
+    fn write_next_mut_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &mut T)>) -> u64;
 }
 /// Ensure `CyclerWriterMutClone` can be trait object
 impl<T> dyn CyclerWriterMutFn<T> where T: WriteAccess {}
 
+/// This trait enables the write half of the cycler to move to the next block using a clone function that is also given a `CloneContext`.
+/// The context tells the function how stale the block being cloned into is, allowing it to only copy what changed since.
+/// The function arguments are (to, from, context)
+/// Every function returns the version the previously written block was published as.
+pub trait CyclerWriterContextFn<T>: CyclerWriter<T>
+where
+    T: WriteAccess,
+{
+    /// Moves the writer to the next block cloning using an `fn` pointer.
+    /// Arguments are (to, from, context)
+    /// This function uses an `fn` pointer so has no additional runtime cost and can be called on a trait object.
+    fn write_next_context_fn(&mut self, clone_fn: fn(&mut T, &T, CloneContext)) -> u64;
+    /// Moves the writer to the next block cloning using an `FnOnce` impl
+    /// Arguments are (to, from, context)
+    /// This function is generic over the function reducing runtime cost but cannot be called on trait objects.
+    fn write_next_context_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64
+    where
+        Self: Sized;
+    /// Moves the writer to the next block cloning using an `FnMut` dynamic reference.
+    /// Arguments are (to, from, context)
+    /// This function takes a dyn pointer so a v-table lookup is necessary.
+    /// `write_next_context_fn` is preferred if the function can be an `fn` pointer or `write_next_context_fn_impl` is preferred if self is sized.
+    fn write_next_context_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T, CloneContext)) -> u64;
+    /// Moves the writer to the next block cloning using a boxed `FnOnce`
+    /// Arguments are (to, from, context)
+    /// This function takes a boxed dyn pointer so a v-table lookup is necessary.
+    /// `write_next_context_fn_dyn` is preferred if the function can be coerced into an `FnMut` as no heap allocation will be necessary.
+    #[allow(clippy::type_complexity)]
+    fn write_next_context_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T, CloneContext)>) -> u64;
+}
+/// Ensure `CyclerWriterContextFn` can be trait object
+impl<T> dyn CyclerWriterContextFn<T> where T: WriteAccess {}
+
+/// This trait enables the write half of the cycler to record changes and move to the next block by replaying the changes the next block missed.
+/// Blocks that have never been published or missed more changes than the change log remembers are cloned with `Clone::clone_from` instead.
+/// For the replayed blocks to match all changes must be made through `write_change`.
+pub trait CyclerWriterReplay<T>: CyclerWriter<T>
+where
+    T: WriteAccess + ReplayChanges,
+{
+    /// Applies `change` to the current block and records it in the block's change log.
+    fn write_change(&mut self, change: T::Change);
+    /// Moves the writer to the next block replaying the changes it missed from the previously written block.
+    /// Returns the version the previously written block was published as.
+    fn write_next_replay(&mut self) -> u64;
+}
+/// Ensure `CyclerWriterReplay` can be trait object
+impl<T> dyn CyclerWriterReplay<T> where T: WriteAccess + ReplayChanges {}
+
 /// This trait is a collection of all the primarily supported writer traits.
 /// Other traits may be added to this in the future but none will be taken away without a major version bump.
 /// Other traits may also be added that do not fall under this for more specific functionality (ex: `CyclerWriterMutFn`).
-pub trait UniversalCyclerWriter<T>: CyclerWriterFn<T> + CyclerWriterDefault<T>
+/// Implemented for every writer implementing all of them so code can be generic over any cycler, or use it as a trait object.
+pub trait UniversalCyclerWriter<T>:
+    CyclerWriterFn<T> + CyclerWriterMutFn<T> + CyclerWriterDefault<T> + CyclerWriterContextFn<T>
 where
-    T: WriteAccess + Clone,
+    T: WriteAccess,
+{
+}
+impl<T, W> UniversalCyclerWriter<T> for W
+where
+    T: WriteAccess,
+    W: CyclerWriterFn<T> + CyclerWriterMutFn<T> + CyclerWriterDefault<T> + CyclerWriterContextFn<T> + ?Sized,
 {
 }
 /// Ensure `UniversalCyclerWriter` can be trait object
 impl<T> dyn UniversalCyclerWriter<T> where T: WriteAccess {}
 
 /// This trait is implemented for the read half of a cycler.
+/// Every block the writer publishes is given a version, starting at 1 and increasing by 1 each publish.
+/// The initial block readers start on is version 0.
+/// A reader's version never decreases, every `read_latest` moves to a block at least as new as the one it held.
 pub trait CyclerReader<T>: ReadAccess<Read = T::Read>
 where
     T: ReadAccess,
 {
     /// Moves the reader to the most up-to-date block at the time of call.
     /// This may be the same block as previously read which means the writer has not published a new block in the time since the last call.
-    fn read_latest(&mut self);
+    /// The new block is never older than the previous one.
+    /// Returns true if the version of the block changed.
+    fn read_latest(&mut self) -> bool;
+    /// Gets the version of the block currently being read.
+    fn version(&self) -> u64;
 }
 /// Ensure `CyclerReader` can be trait object
 impl<T> dyn CyclerReader<T> where T: ReadAccess {}
 
+/// This trait enables the read half of a cycler to wait for the writer to publish a new block without blocking or spinning.
+/// Waiting is runtime agnostic, the writer wakes the registered tasks directly when it publishes.
+pub trait CyclerReaderAsync<T>: CyclerReader<T>
+where
+    T: ReadAccess,
+{
+    /// Moves the reader to the most up-to-date block if a version newer than the current block has been published.
+    /// Otherwise the task in `cx` is woken when the writer publishes its next block.
+    fn poll_read_latest(&mut self, cx: &mut Context<'_>) -> Poll<()>;
+    /// Stops waking the task registered by `poll_read_latest`, called when a `ReadLatestFuture` is dropped.
+    /// Does nothing by default, a task woken after its future is gone only gets a spurious wake up.
+    fn cancel_read_latest(&mut self) {}
+    /// Returns a future that resolves once the reader has moved to a version newer than the one it currently holds.
+    fn read_latest_async(&mut self) -> ReadLatestFuture<'_, T, Self>
+    where
+        Self: Sized,
+    {
+        ReadLatestFuture::new(self)
+    }
+}
+/// Ensure `CyclerReaderAsync` can be trait object
+impl<T> dyn CyclerReaderAsync<T> where T: ReadAccess {}
+
+/// This trait enables the read half of a cycler to block until the writer publishes a new block.
+/// Waiting threads are parked so an idle reader uses no CPU.
+pub trait CyclerReaderWait<T>: CyclerReader<T>
+where
+    T: ReadAccess,
+{
+    /// Parks the current thread until a version newer than the current block has been published or `timeout` passes.
+    /// Returns true if a new block is available, the reader is not moved so `read_latest` must still be called.
+    fn wait_for_update(&self, timeout: Duration) -> bool;
+    /// Parks the current thread until a version newer than the current block has been published or `deadline` passes.
+    /// Returns true if a new block is available, the reader is not moved so `read_latest` must still be called.
+    fn wait_for_update_until(&self, deadline: Instant) -> bool;
+}
+/// Ensure `CyclerReaderWait` can be trait object
+impl<T> dyn CyclerReaderWait<T> where T: ReadAccess {}
+
 /// This trait is a collection of all the primarily supported reader traits.
 /// Other traits may be added to this in the future but none will be taken away without a major version bump.
 /// Other traits may also be added that do not fall under this for more specific functionality.
-pub trait UniversalCyclerReader<T>:
-    CyclerWriterFn<T> + CyclerWriterMutFn<T> + CyclerWriterDefault<T>
+/// Implemented for every reader implementing all of them so code can be generic over any cycler, or use it as a trait object.
+pub trait UniversalCyclerReader<T>: CyclerReaderAsync<T> + CyclerReaderWait<T>
 where
-    T: WriteAccess,
+    T: ReadAccess,
+{
+}
+impl<T, R> UniversalCyclerReader<T> for R
+where
+    T: ReadAccess,
+    R: CyclerReaderAsync<T> + CyclerReaderWait<T> + ?Sized,
 {
 }
 /// Ensure `UniversalCyclerReader` can be trait object
-impl<T> dyn UniversalCyclerReader<T> where T: WriteAccess {}
+impl<T> dyn UniversalCyclerReader<T> where T: ReadAccess {}
@@ -0,0 +1,463 @@
+//! The `TripleBufferCycler` is a single reader cycler that needs no lock around its slots.
+//! Of its three slots the writer owns one, the reader owns one and the third is shared between them.
+//! Publishing swaps the writer's slot with the shared one and reading the latest swaps the reader's, so every switch is a single atomic operation on the shared index.
+//! It can't have more than one reader, readers can't be added or cloned.
+//!
+//! ## Memory ordering
+//! The shared index is only ever swapped, each side handing over the slot it is done with and taking the other.
+//! Both swaps are `AcqRel`: the writer's releases the block it publishes and acquires the reader's last reads of the slot it takes, and the reader's does the opposite.
+
+use crate::cache_padded::CachePadded;
+use crate::change_log::replay_into;
+use crate::notifier::{PublishNotifier, WakerSlot};
+use crate::slot::{CloneContext, Slot};
+use crate::sync::{AtomicUsize, UnsafeCell};
+use crate::traits::*;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// The bits of the shared state holding the index of the shared slot.
+const INDEX: usize = 0b11;
+/// Set when the shared slot was published after the reader took its slot, the reader only takes the shared slot while this is set.
+const NEW: usize = 0b100;
+/// Set while the writer changes the shared slot in `publish_mut`, never at the same time as `NEW`.
+const BUSY: usize = 0b1000;
+
+/// The slots and shared index of a triple buffer.
+struct TripleBuffer<T> {
+    slots: [UnsafeCell<Slot<T>>; 3],
+    /// The index of the shared slot and the `NEW` and `BUSY` flags, swapped by every switch.
+    shared: CachePadded<AtomicUsize>,
+    notifier: PublishNotifier,
+}
+impl<T> TripleBuffer<T> {
+    /// Gets shared access to slot `index`.
+    /// Safety: the caller must own the slot, or it must be the slot the writer just published which is only read until it is taken back.
+    #[inline]
+    unsafe fn slot(&self, index: usize) -> &Slot<T> {
+        self.slots[index].with(|slot| &*slot)
+    }
+
+    /// Gets exclusive access to slot `index`.
+    /// Safety: the caller must be the writer and own the slot.
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn slot_mut(&self, index: usize) -> &mut Slot<T> {
+        self.slots[index].with_mut(|slot| &mut *slot)
+    }
+}
+// Safety: a slot is only written by the writer while it owns it, the slots are otherwise shared between the writer and reader.
+unsafe impl<T> Sync for TripleBuffer<T> where T: Send + Sync {}
+
+/// The writer to a `TripleBufferCycler`
+pub struct TripleBufferCyclerWriter<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    /// The slot owned by the writer.
+    index: usize,
+}
+impl<T> TripleBufferCyclerWriter<T> {
+    #[inline]
+    fn slot(&self) -> &Slot<T> {
+        // Safety: the writer owns its slot.
+        unsafe { self.buffer.slot(self.index) }
+    }
+
+    #[inline]
+    fn slot_mut(&mut self) -> &mut Slot<T> {
+        // Safety: the writer owns its slot, `&mut self` keeps any other borrow of it from existing.
+        unsafe { self.buffer.slot_mut(self.index) }
+    }
+
+    /// Publishes the writer's slot and takes the shared one, returning the version published.
+    /// `clone_fn` is given the newly taken slot, the slot being published and the index of the newly taken slot.
+    fn publish(&mut self, clone_fn: impl FnOnce(&mut Slot<T>, &Slot<T>, usize)) -> u64 {
+        let version = self.buffer.notifier.latest_version() + 1;
+        self.slot_mut().version = version;
+        let published = self.index;
+        self.index = self.buffer.shared.swap(published | NEW, Ordering::AcqRel) & INDEX;
+        // Safety: the writer owns the taken slot, the published slot may be read by the reader from now on so it is only read.
+        let (to, from) = unsafe { (self.buffer.slot_mut(self.index), self.buffer.slot(published)) };
+        clone_fn(to, from, self.index);
+        self.buffer.notifier.notify(version);
+        version
+    }
+
+    /// Like `publish` but `clone_fn` gets exclusive access to the slot being published, which is published after it returns.
+    /// The shared slot is taken marked `BUSY` so the reader can't take either slot until the writer is done with both.
+    fn publish_mut(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64 {
+        let version = self.buffer.notifier.latest_version() + 1;
+        let published = self.index;
+        let next = self.buffer.shared.swap(published | BUSY, Ordering::AcqRel) & INDEX;
+        // Safety: the reader only takes the shared slot while it is `NEW`, so the writer owns both until it clears `BUSY`.
+        let (to, from) = unsafe { (self.buffer.slot_mut(next), self.buffer.slot_mut(published)) };
+        clone_fn(to.data_mut(), from.data_mut());
+        from.version = version;
+        self.buffer.shared.store(published | NEW, Ordering::Release);
+        self.index = next;
+        self.buffer.notifier.notify(version);
+        version
+    }
+
+    /// Publishes calling `clone_fn` with a `CloneContext` for the newly taken slot.
+    fn publish_context(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64 {
+        self.publish(|to, from, slot| {
+            let context = CloneContext { target_version: to.version, source_version: from.version, slot };
+            clone_fn(to.data_mut(), from.data(), context)
+        })
+    }
+}
+impl<T> Debug for TripleBufferCyclerWriter<T> where T: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TripleBufferCyclerWriter").field("index", &self.index).field("slot", self.slot()).finish()
+    }
+}
+impl<T> ReadAccess for TripleBufferCyclerWriter<T> where T: ReadAccess {
+    type Read = T::Read;
+
+    /// Gets a shared reference to the read data of the current block
+    #[inline]
+    fn read_data(&self) -> &Self::Read {
+        self.slot().data().read_data()
+    }
+}
+impl<T> WriteAccess for TripleBufferCyclerWriter<T> where T: WriteAccess {
+    type Write = T::Write;
+
+    /// Gets a shared reference to the write data of the current block
+    #[inline]
+    fn write_data(&self) -> &Self::Write {
+        self.slot().data().write_data()
+    }
+
+    /// Gets an exclusive reference to the write data of the current block
+    #[inline]
+    fn write_data_mut(&mut self) -> &mut Self::Write {
+        self.slot_mut().data_mut().write_data_mut()
+    }
+}
+impl<T> CyclerWriter<T> for TripleBufferCyclerWriter<T> where T: WriteAccess {}
+impl<T> CyclerWriterFn<T> for TripleBufferCyclerWriter<T> where T: WriteAccess {
+    fn write_next_fn(&mut self, clone_fn: fn(&mut T, &T)) -> u64 {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+
+    fn write_next_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T)) -> u64 where Self: Sized {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+
+    fn write_next_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T)) -> u64 {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+
+    fn write_next_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T)>) -> u64 {
+        self.publish(|to, from, _| clone_fn(to.data_mut(), from.data()))
+    }
+}
+impl<T> CyclerWriterMutFn<T> for TripleBufferCyclerWriter<T> where T: WriteAccess {
+    fn write_next_mut_fn(&mut self, clone_fn: fn(&mut T, &mut T)) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &mut T)) -> u64 where Self: Sized {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &mut T)) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+
+    fn write_next_mut_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &mut T)>) -> u64 {
+        self.publish_mut(clone_fn)
+    }
+}
+impl<T> CyclerWriterDefault<T> for TripleBufferCyclerWriter<T> where T: Clone + WriteAccess {
+    fn write_next(&mut self) -> u64 {
+        self.write_next_fn(T::clone_from)
+    }
+}
+impl<T> CyclerWriterContextFn<T> for TripleBufferCyclerWriter<T> where T: WriteAccess {
+    fn write_next_context_fn(&mut self, clone_fn: fn(&mut T, &T, CloneContext)) -> u64 {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_impl(&mut self, clone_fn: impl FnOnce(&mut T, &T, CloneContext)) -> u64 where Self: Sized {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_dyn(&mut self, clone_fn: &mut dyn FnMut(&mut T, &T, CloneContext)) -> u64 {
+        self.publish_context(clone_fn)
+    }
+
+    fn write_next_context_fn_dyn_boxed(&mut self, clone_fn: Box<dyn FnOnce(&mut T, &T, CloneContext)>) -> u64 {
+        self.publish_context(clone_fn)
+    }
+}
+impl<T> CyclerWriterReplay<T> for TripleBufferCyclerWriter<T> where T: WriteAccess + ReplayChanges {
+    fn write_change(&mut self, change: T::Change) {
+        let version = self.buffer.notifier.latest_version() + 1;
+        let data = self.slot_mut().data_mut();
+        data.apply_change(&change);
+        data.change_log_mut().record(version, change);
+    }
+
+    fn write_next_replay(&mut self) -> u64 {
+        self.write_next_context_fn(|to, from, context| replay_into(to, context.target_version, from))
+    }
+}
+
+/// The reader for a `TripleBufferCycler`
+pub struct TripleBufferCyclerReader<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    /// The slot owned by the reader.
+    index: usize,
+    waker: WakerSlot,
+}
+impl<T> TripleBufferCyclerReader<T> {
+    #[inline]
+    fn slot(&self) -> &Slot<T> {
+        // Safety: the reader owns its slot.
+        unsafe { self.buffer.slot(self.index) }
+    }
+}
+impl<T> Drop for TripleBufferCyclerReader<T> {
+    fn drop(&mut self) {
+        self.buffer.notifier.unregister(&mut self.waker);
+    }
+}
+impl<T> Debug for TripleBufferCyclerReader<T> where T: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TripleBufferCyclerReader").field("slot", self.slot()).finish()
+    }
+}
+impl<T> ReadAccess for TripleBufferCyclerReader<T> where T: ReadAccess {
+    type Read = T::Read;
+
+    #[inline]
+    fn read_data(&self) -> &Self::Read {
+        self.slot().data().read_data()
+    }
+}
+impl<T> CyclerReader<T> for TripleBufferCyclerReader<T> where T: ReadAccess {
+    fn read_latest(&mut self) -> bool {
+        let mut shared = self.buffer.shared.load(Ordering::Relaxed);
+        // The shared slot is always newer than the reader's while it is `NEW`, so the version never goes backwards.
+        while shared & NEW != 0 {
+            match self.buffer.shared.compare_exchange_weak(shared, self.index, Ordering::AcqRel, Ordering::Relaxed) {
+                Ok(_) => {
+                    self.index = shared & INDEX;
+                    return true;
+                }
+                Err(current) => shared = current,
+            }
+            crate::sync::spin_loop();
+        }
+        false
+    }
+
+    #[inline]
+    fn version(&self) -> u64 {
+        self.slot().version
+    }
+}
+impl<T> TripleBufferCyclerReader<T> {
+    /// The version to wait for a newer one than, given the latest version loaded before checking for a `NEW` slot.
+    /// The latest version is newer than the reader's while `publish_mut` holds it `BUSY`, the slot is only `NEW` once the next version is notified.
+    #[inline]
+    fn seen(&self, latest_version: u64) -> u64 {
+        latest_version.max(self.slot().version)
+    }
+
+    /// Waits until the shared slot is `NEW` or `deadline` passes, returning whether it is.
+    fn wait_until(&self, deadline: Option<Instant>) -> bool {
+        loop {
+            let latest_version = self.buffer.notifier.latest_version();
+            if self.buffer.shared.load(Ordering::Relaxed) & NEW != 0 {
+                return true;
+            }
+            if !self.buffer.notifier.wait_newer_than(self.seen(latest_version), deadline) {
+                return self.buffer.shared.load(Ordering::Relaxed) & NEW != 0;
+            }
+        }
+    }
+}
+impl<T> CyclerReaderAsync<T> for TripleBufferCyclerReader<T> where T: ReadAccess {
+    fn poll_read_latest(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            let latest_version = self.buffer.notifier.latest_version();
+            if self.read_latest() {
+                return Poll::Ready(());
+            }
+            let seen = self.seen(latest_version);
+            if !self.buffer.notifier.poll_newer_than(&mut self.waker, seen, cx) {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    fn cancel_read_latest(&mut self) {
+        self.buffer.notifier.unregister(&mut self.waker);
+    }
+}
+impl<T> CyclerReaderWait<T> for TripleBufferCyclerReader<T> where T: ReadAccess {
+    fn wait_for_update(&self, timeout: Duration) -> bool {
+        self.wait_until(Instant::now().checked_add(timeout))
+    }
+
+    fn wait_for_update_until(&self, deadline: Instant) -> bool {
+        self.wait_until(Some(deadline))
+    }
+}
+
+impl<T> EnsureSend for TripleBufferCyclerWriter<T> where T: Send + Sync {}
+impl<T> EnsureSync for TripleBufferCyclerWriter<T> where T: Send + Sync {}
+impl<T> EnsureSend for TripleBufferCyclerReader<T> where T: Send + Sync {}
+impl<T> EnsureSync for TripleBufferCyclerReader<T> where T: Send + Sync {}
+
+/// Creates a TripleBufferCycler using `values` as the initial values for the slots.
+/// The reader starts reading the first value and the writer starts writing the second.
+pub fn build_single_reader<T>(values: [T; 3]) -> (TripleBufferCyclerWriter<T>, TripleBufferCyclerReader<T>) {
+    let buffer = Arc::new(TripleBuffer {
+        slots: values.map(|value| UnsafeCell::new(Slot::new(value))),
+        shared: CachePadded::new(AtomicUsize::new(2)),
+        notifier: PublishNotifier::default(),
+    });
+    (TripleBufferCyclerWriter { buffer: buffer.clone(), index: 1 }, TripleBufferCyclerReader { buffer, index: 0, waker: WakerSlot::default() })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::{async_test, block_on, clone_fn_test, context_test, mut_fn_test, replay_test, single_reader_test, stress_test, version_test, wait_test, TestData};
+    use crate::triple_buffer_cycler::{build_single_reader, TripleBufferCyclerReader, TripleBufferCyclerWriter, NEW};
+    use crate::{CyclerReader, CyclerReaderAsync, CyclerReaderWait, CyclerWriterDefault, CyclerWriterMutFn, ReadAccess, WriteAccess};
+    use std::sync::atomic::Ordering;
+    use std::task::{Context, Waker};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Builds from the first three values as the suite does for a multi reader cycler with a single reader.
+    fn build<T>(values: Vec<T>) -> (TripleBufferCyclerWriter<T>, Vec<TripleBufferCyclerReader<T>>) {
+        let mut values = values.into_iter();
+        let (writer, reader) = build_single_reader([(); 3].map(|_| values.next().unwrap()));
+        (writer, vec![reader])
+    }
+
+    #[test]
+    fn default_test() {
+        let (mut writer, mut reader) = build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+        assert_eq!((writer.index, reader.index), (1, 0));
+        let new_data = TestData { test1: 100, test2: "Test2".to_string(), test3: Box::new(1002) };
+        writer.write_data_mut().clone_from(&new_data);
+        assert_eq!(writer.write_next(), 1);
+        // The published slot is shared and the writer took the one that was.
+        assert_eq!(writer.buffer.shared.load(Ordering::SeqCst), 1 | NEW);
+        assert_eq!(writer.index, 2);
+        assert_eq!(*writer.write_data(), new_data);
+        assert_eq!(*reader.read_data(), TestData::default());
+        assert!(reader.wait_for_update(Duration::ZERO));
+        assert!(reader.read_latest());
+        assert_eq!(reader.index, 1);
+        assert_eq!(writer.buffer.shared.load(Ordering::SeqCst), 0);
+        assert_eq!(*reader.read_data(), new_data);
+        assert!(!reader.read_latest());
+        assert_eq!(reader.version(), 1);
+
+        // Publishing twice before the reader switches skips the first block.
+        writer.write_data_mut().test1 = 2;
+        writer.write_next();
+        writer.write_data_mut().test1 = 3;
+        writer.write_next_mut_fn(|to, from| to.clone_from(from));
+        assert!(reader.read_latest());
+        assert_eq!((reader.version(), reader.read_data().test1), (3, 3));
+        drop(writer);
+        assert!(!reader.read_latest());
+    }
+
+    #[test]
+    fn wait_during_publish_mut_test() {
+        let (mut writer, mut reader) = build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+        writer.write_data_mut().test1 = 1;
+        writer.write_next();
+        // The unread block is taken back `BUSY`, the reader has nothing newer until the mutable publish finishes.
+        writer.write_next_mut_fn_impl(|to, from| {
+            assert!(!reader.wait_for_update(Duration::ZERO));
+            assert!(reader.poll_read_latest(&mut Context::from_waker(Waker::noop())).is_pending());
+            assert_eq!(reader.version(), 0);
+            to.clone_from(from);
+        });
+        assert!(reader.wait_for_update(Duration::ZERO));
+        assert!(reader.poll_read_latest(&mut Context::from_waker(Waker::noop())).is_ready());
+        assert_eq!(reader.version(), 2);
+
+        // A reader waiting across a mutable publish is woken by it and moves to it.
+        let handle = thread::spawn(move || {
+            block_on(reader.read_latest_async());
+            reader.version()
+        });
+        writer.write_next_mut_fn_impl(|to, from| {
+            thread::sleep(Duration::from_millis(20));
+            to.clone_from(from);
+        });
+        assert_eq!(handle.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn suite_test() {
+        single_reader_test(build);
+        version_test(build);
+        clone_fn_test(build);
+        mut_fn_test(build);
+        context_test(build);
+        async_test(build);
+        wait_test(build);
+        replay_test(build);
+        stress_test(build);
+    }
+
+    #[test]
+    fn default_cycler_test() {
+        let (mut writer, mut reader): (TripleBufferCyclerWriter<TestData>, TripleBufferCyclerReader<TestData>) = crate::build_single_reader_default();
+        writer.write_data_mut().test1 = 1;
+        writer.write_next();
+        assert!(reader.read_latest());
+        assert_eq!(reader.read_data().test1, 1);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use crate::testing::TestData;
+    use crate::triple_buffer_cycler::build_single_reader;
+    use crate::{CyclerReader, CyclerWriterDefault, CyclerWriterMutFn, ReadAccess, WriteAccess};
+    use loom::thread;
+
+    #[test]
+    fn loom_publish_test() {
+        loom::model(|| {
+            let (mut writer, mut reader) = build_single_reader([TestData::default(), TestData::default(), TestData::default()]);
+            let writer = thread::spawn(move || {
+                for version in 1..=3 {
+                    writer.write_data_mut().test1 = version;
+                    // Both kinds of publish hand slots over differently.
+                    if version == 2 {
+                        writer.write_next_mut_fn(|to, from| to.clone_from(from));
+                    } else {
+                        writer.write_next();
+                    }
+                }
+            });
+            let mut last_version = 0;
+            for _ in 0..2 {
+                reader.read_latest();
+                assert!(reader.version() >= last_version);
+                assert_eq!(reader.read_data().test1 as u64, reader.version());
+                last_version = reader.version();
+            }
+            writer.join().unwrap();
+            reader.read_latest();
+            assert_eq!(reader.read_data().test1, 3);
+        });
+    }
+}